@@ -0,0 +1,106 @@
+use crate::notif::{NotifEvent, EventKind};
+use crate::config::Config;
+use crate::launch::open_url;
+use crate::history::{self, HistoryEntry, HistoryAction};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+// Buttons that can be put on a notification
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotifAction {
+    Open,
+    CopyLink,
+    Archive,
+    Mute
+}
+
+impl NotifAction {
+    // The actions that make sense for this video
+    pub fn available(kind: EventKind) -> Vec<NotifAction> {
+        let mut actions = vec! [NotifAction::Open, NotifAction::CopyLink];
+        if kind == EventKind::Live { actions.push(NotifAction::Archive); }
+        actions.push(NotifAction::Mute);
+        actions
+    }
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            NotifAction::Open => "open",
+            NotifAction::CopyLink => "copy-link",
+            NotifAction::Archive => "archive",
+            NotifAction::Mute => "mute"
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotifAction::Open => "Open",
+            NotifAction::CopyLink => "Copy link",
+            NotifAction::Archive => "Archive",
+            NotifAction::Mute => "Mute channel"
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<NotifAction> {
+        match id {
+            "open" => Some(NotifAction::Open),
+            "copy-link" => Some(NotifAction::CopyLink),
+            "archive" => Some(NotifAction::Archive),
+            "mute" => Some(NotifAction::Mute),
+            _ => None
+        }
+    }
+}
+
+// Do whatever the user clicked on
+pub fn run_action(action: NotifAction, event: &NotifEvent, config: &Config, cfg_path: &Path) {
+    let url = event.watch_url();
+    let vid = &event.video;
+    let channel = &event.channel;
+
+    match action {
+        NotifAction::Open => {
+            if open_url(&config.open_command, &url).is_err() {
+                eprintln! ("Could not run \"{}\" to open {}", config.open_command, url);
+            }
+        },
+        NotifAction::CopyLink => {
+            if copy_to_clipboard(&config.copy_command, &url).is_err() {
+                eprintln! ("Could not run \"{}\" to copy {}", config.copy_command, url);
+            }
+        },
+        NotifAction::Archive => {
+            let out_file = crate::archive_file_path(&config.archive_path, vid);
+            if out_file.exists() {
+                println! ("{} is already being archived", vid.video_title);
+            } else {
+                println! ("Archiving {} to {}", vid.video_title, out_file.display());
+                history::record_or_warn(cfg_path, &HistoryEntry::new(HistoryAction::Archived, event.kind, vid, channel, chrono::Utc::now().timestamp()));
+                crate::archive_stream(vid.video_id.clone(), String::from(out_file.to_str().unwrap()));
+            }
+        },
+        NotifAction::Mute => {
+            if channel.mute_for(config.mute_hours * 60 * 60).is_err() {
+                eprintln! ("Could not mute {}", channel.name);
+            } else {
+                println! ("Muted {} for {} hours", channel.name, config.mute_hours);
+            }
+        }
+    }
+}
+
+// Pipe the URL into the configured clipboard tool
+fn copy_to_clipboard(command: &str, url: &str) -> Result<(), ()> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or(())?;
+
+    let mut child = Command::new(program).args(parts)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|_| ())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(url.as_bytes()).map_err(|_| ())?;
+    }
+    child.wait().map(|_| ()).map_err(|_| ())
+}
@@ -0,0 +1,107 @@
+extern crate easy_http_request;
+extern crate image;
+extern crate regex;
+use easy_http_request::DefaultHttpRequest;
+use crate::youtube::Channel;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+// Shown when we can't get a channel's real avatar
+const FALLBACK_ICON: &[u8] = include_bytes!("../assets/fallback-icon.png");
+// Avatars are saved as squares of this many pixels
+const ICON_SIZE: u32 = 128;
+// How long a downloaded avatar is used before it's fetched again
+const AVATAR_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+// How long to wait before trying again when we're showing the fallback
+const FALLBACK_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+// Avatars and channel pages are bigger than easy_http_request's 1 MiB default
+const MAX_DOWNLOAD_SIZE: usize = 8 * 1024 * 1024;
+
+// Download the channel's avatar to its pic_path, falling back to the bundled icon
+pub fn refresh_avatar(channel: &Channel) -> Result<(), ()> {
+    if let Ok(url) = fetch_avatar_url(channel) {
+        if let Ok(bytes) = download(&url) {
+            if save_as_png(&bytes, &channel.pic_path).is_ok() {
+                // Webhooks can't use the local file, so remember where it came from
+                let _ = std::fs::write(channel.pic_path.with_extension("url"), url);
+                let _ = std::fs::remove_file(channel.pic_path.with_extension("failed"));
+                return Ok(());
+            }
+        }
+    }
+
+    // Keep an old avatar over the fallback; it's probably still right
+    if !channel.pic_path.exists() { let _ = std::fs::write(&channel.pic_path, FALLBACK_ICON); }
+    // Either way the file's no newer, so note when we tried to stop trying again every loop
+    let _ = std::fs::write(channel.pic_path.with_extension("failed"), "");
+    Err(())
+}
+
+// Get the URL the channel's avatar was last downloaded from
+pub fn avatar_url(channel: &Channel) -> Option<String> {
+    std::fs::read_to_string(channel.pic_path.with_extension("url")).ok()
+}
+
+// Refresh the avatar if it's missing or old
+pub fn refresh_if_stale(channel: &Channel) {
+    // Give the last failed attempt as long as the fallback before trying again
+    if age(&channel.pic_path.with_extension("failed")).is_some_and(|age| age <= FALLBACK_MAX_AGE) { return; }
+
+    let max_age = if is_fallback(&channel.pic_path) { FALLBACK_MAX_AGE } else { AVATAR_MAX_AGE };
+    let stale = match age(&channel.pic_path) {
+        Some(age) => age > max_age,
+        None => true
+    };
+    if stale && refresh_avatar(channel).is_err() {
+        eprintln! ("Could not download the avatar for {}; using the fallback icon", channel.name);
+    }
+}
+
+// Find the avatar's URL on the channel page
+fn fetch_avatar_url(channel: &Channel) -> Result<String, ()> {
+    let page = String::from_utf8(download(&channel.get_channel_url())?).map_err(|_| ())?;
+
+    let og_re = regex::Regex::new(r#"<meta property="og:image" content="([^"]+)""#).unwrap();
+    let json_re = regex::Regex::new(r#""avatar":\{"thumbnails":\[\{"url":"([^"]+)""#).unwrap();
+    if let Some(caps) = og_re.captures(&page).or_else(|| json_re.captures(&page)) {
+        // Ask for a size close to what we need instead of the 900px default
+        let size_re = regex::Regex::new(r"=s\d+-").unwrap();
+        return Ok(size_re.replace(&caps[1], "=s256-").into_owned());
+    }
+    Err(())
+}
+
+fn download(url: &str) -> Result<Vec<u8>, ()> {
+    let mut request = DefaultHttpRequest::get_from_url_str(url).map_err(|_| ())?;
+    request.options.max_response_body_size = MAX_DOWNLOAD_SIZE;
+
+    // Skip the EU cookie consent page
+    let mut headers = HashMap::new();
+    headers.insert(String::from("Cookie"), String::from("CONSENT=YES+1"));
+    request.headers = Some(headers);
+
+    let response = request.send().map_err(|_| ())?;
+    if response.status_code == 200 { Ok(response.body) } else { Err(()) }
+}
+
+// Resize whatever YouTube gave us and save it as a PNG
+fn save_as_png(bytes: &[u8], path: &Path) -> Result<(), ()> {
+    let img = image::load_from_memory(bytes).map_err(|_| ())?;
+    let resized = img.resize_to_fill(ICON_SIZE, ICON_SIZE, image::imageops::FilterType::Lanczos3);
+
+    // Write next to it and move it over, so a notification never sees half a file
+    let tmp_path = path.with_extension("png.tmp");
+    resized.save_with_format(&tmp_path, image::ImageFormat::Png).map_err(|_| ())?;
+    std::fs::rename(&tmp_path, path).map_err(|_| ())
+}
+
+// How long ago the file was last written
+fn age(path: &Path) -> Option<Duration> {
+    let modified = std::fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+    SystemTime::now().duration_since(modified).ok()
+}
+
+fn is_fallback(path: &Path) -> bool {
+    if let Ok(bytes) = std::fs::read(path) { bytes == FALLBACK_ICON } else { false }
+}
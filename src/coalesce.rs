@@ -0,0 +1,189 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use crate::config;
+use crate::notif::{self, Notifier, NotifEvent};
+use std::collections::VecDeque;
+use std::path::{PathBuf, Path};
+
+const HOUR: i64 = 60 * 60;
+const PENDING_FILE_NAME: &str = "pending.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Coalescing {
+    // Events from one channel this many seconds apart are sent as one notification; 0 turns it off
+    pub channel_window_secs: u64,
+    // The same across every channel; wins over channel_window_secs when both are set
+    pub global_window_secs: u64,
+    // Anything over this is held until the hour's up, then sent together; no cap if None
+    pub max_per_hour: Option<u32>
+}
+
+impl Default for Coalescing {
+    fn default() -> Coalescing {
+        Coalescing {
+            channel_window_secs: 60,
+            global_window_secs: 0,
+            max_per_hour: None
+        }
+    }
+}
+
+// Holds events for a little while so bursts go out as one notification
+pub struct Coalescer {
+    config: Coalescing,
+    // Where pending is kept, so a restart doesn't lose anything still waiting
+    path: PathBuf,
+    // Events waiting to go out, with when they came in
+    pending: Vec<(i64, NotifEvent)>,
+    // When each notification in the last hour went out, for max_per_hour
+    sent: VecDeque<i64>
+}
+
+impl Coalescer {
+    pub fn load(cfg_path: &Path, config: &Coalescing) -> Coalescer {
+        let mut path = config::state_dir(cfg_path);
+        path.push(PENDING_FILE_NAME);
+
+        let pending = config::load_state(&path, "pending notifications");
+        Coalescer {
+            config: config.clone(),
+            path,
+            pending,
+            sent: VecDeque::new()
+        }
+    }
+
+    pub fn push(&mut self, event: NotifEvent, now: i64) {
+        // A video only needs telling about once
+        if self.pending.iter().any(|(_, pending)| pending.video.video_id == event.video.video_id && pending.kind == event.kind) { return; }
+        self.pending.push((now, event));
+        self.save_or_warn();
+    }
+
+    // Send every group of events that's waited out its window, as long as the cap allows
+    pub fn flush(&mut self, notifiers: &[Box<dyn Notifier>], now: i64) {
+        while let Some(front) = self.sent.front() {
+            if now - front >= HOUR { self.sent.pop_front(); } else { break; }
+        }
+
+        let mut ready = self.ready_groups(now);
+        while !ready.is_empty() {
+            let left = self.config.max_per_hour.map(|max| (max as usize).saturating_sub(self.sent.len()));
+            if left == Some(0) {
+                println! ("Sent {} notifications in the last hour; holding the rest", self.sent.len());
+                break;
+            }
+            // Squeeze everything into the last one we're allowed
+            let group: Vec<String> = if left == Some(1) { ready.drain(..).flatten().collect() } else { ready.remove(0) };
+
+            let (events, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending).into_iter()
+                .partition(|(_, event)| group.contains(&event.video.video_id));
+            self.pending = rest;
+            self.save_or_warn();
+            let events: Vec<NotifEvent> = events.into_iter().map(|(_, event)| event).collect();
+            self.send(notifiers, &events, now);
+        }
+    }
+
+    // Send an event straight away; it still counts towards the cap
+    pub fn send_now(&mut self, notifiers: &[Box<dyn Notifier>], event: &NotifEvent, now: i64) {
+        notif::dispatch(notifiers, event);
+        self.sent.push_back(now);
+    }
+
+    // Send a digest straight away; it still counts towards the cap
+    pub fn send_digest(&mut self, notifiers: &[Box<dyn Notifier>], title: &str, events: &[NotifEvent], now: i64) {
+        notif::dispatch_digest(notifiers, title, events);
+        self.sent.push_back(now);
+    }
+
+    fn send(&mut self, notifiers: &[Box<dyn Notifier>], events: &[NotifEvent], now: i64) {
+        match events {
+            [] => return,
+            [event] => notif::dispatch(notifiers, event),
+            _ => notif::dispatch_digest(notifiers, &digest_title(events), events)
+        }
+        self.sent.push_back(now);
+    }
+
+    fn save_or_warn(&self) {
+        if config::save_state(&self.path, &self.pending).is_err() {
+            eprintln! ("Could not save the pending notifications to {}", self.path.display());
+        }
+    }
+
+    // The video ids of each group of pending events whose window has passed
+    fn ready_groups(&self, now: i64) -> Vec<Vec<String>> {
+        if self.config.global_window_secs > 0 {
+            let oldest = if let Some((time, _)) = self.pending.first() { *time } else { return Vec::new(); };
+            if now - oldest < self.config.global_window_secs as i64 { return Vec::new(); }
+            return vec! [self.pending.iter().map(|(_, event)| event.video.video_id.clone()).collect()];
+        }
+
+        // One group per channel, in the order they came in
+        let mut groups: Vec<(i64, &str, Vec<String>)> = Vec::new();
+        for (time, event) in self.pending.iter() {
+            match groups.iter_mut().find(|(_, channel_id, _)| *channel_id == event.channel.channel_id) {
+                Some((_, _, group)) => group.push(event.video.video_id.clone()),
+                None => groups.push((*time, &event.channel.channel_id, vec! [event.video.video_id.clone()]))
+            }
+        }
+        groups.into_iter()
+            .filter(|(oldest, _, _)| now - oldest >= self.config.channel_window_secs as i64)
+            .map(|(_, _, group)| group)
+            .collect()
+    }
+}
+
+// "Channel X uploaded 7 videos", or "12 new videos from 3 channels"
+fn digest_title(events: &[NotifEvent]) -> String {
+    let mut channels: Vec<&str> = events.iter().map(|event| event.channel.name.as_str()).collect();
+    channels.sort_unstable();
+    channels.dedup();
+    if channels.len() == 1 { format! ("{} uploaded {} videos", channels[0], events.len()) }
+    else { format! ("{} new videos from {} channels", events.len(), channels.len()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notif::{testing, Digest, EventKind, NotifyError};
+    use std::sync::{Arc, Mutex};
+
+    // Writes down the video id of everything it's sent
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Notifier for Recorder {
+        fn name(&self) -> &str { "recorder" }
+        fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+            self.0.lock().unwrap().push(event.video.video_id.clone());
+            Ok(())
+        }
+        fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+            self.0.lock().unwrap().extend(digest.events.iter().map(|event| event.video.video_id.clone()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn pending_events_survive_a_restart() {
+        let dir = std::env::temp_dir().join(format! ("yt-notify-coalesce-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let notifiers: Vec<Box<dyn Notifier>> = vec! [Box::new(Recorder(sent.clone()))];
+
+        Coalescer::load(&dir, &Coalescing::default()).push(testing::event(EventKind::Upload), 1000);
+
+        let mut coalescer = Coalescer::load(&dir, &Coalescing::default());
+        coalescer.flush(&notifiers, 1030);
+        assert! (sent.lock().unwrap().is_empty());
+        coalescer.flush(&notifiers, 1060);
+        assert_eq! (*sent.lock().unwrap(), vec! [String::from("dQw4w9WgXcQ")]);
+
+        // Once it's gone out it's not sent again after the next restart
+        Coalescer::load(&dir, &Coalescing::default()).flush(&notifiers, 2000);
+        assert_eq! (sent.lock().unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
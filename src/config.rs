@@ -0,0 +1,182 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use std::path::{PathBuf, Path};
+use crate::notif::{NotifierConfig, Backend};
+use crate::templates::Templates;
+use crate::quiet::QuietHours;
+use crate::coalesce::Coalescing;
+use crate::report::ReportConfig;
+#[cfg(not(feature = "desktop"))]
+use crate::notif::terminal::{TerminalConfig, TerminalStyle};
+
+// Lives next to the channel files, so get_saved_entries has to skip it
+pub const CONFIG_FILE_NAME: &str = "config.json";
+// Anything the daemon keeps for itself goes in here, out of the way of the channel files
+const STATE_DIR_NAME: &str = "state";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    // Attach the video's thumbnail to desktop notifications
+    pub thumbnails: bool,
+    // Largest the thumbnail cache can grow before old ones are evicted, in MiB
+    pub thumbnail_cache_mb: u64,
+    // Run with the watch URL when a notification's "Open" button is clicked
+    pub open_command: String,
+    // Gets the watch URL on stdin when "Copy link" is clicked
+    pub copy_command: String,
+    // Run with the watch URL by the play command
+    pub player_command: String,
+    // An Invidious or Piped instance (like "https://yewtu.be") for play to use; frontend if None
+    pub player_frontend: Option<String>,
+    // An Invidious or Piped instance every link points to instead of YouTube; archiving still uses YouTube
+    pub frontend: Option<String>,
+    // Where streams are archived to
+    pub archive_path: PathBuf,
+    // How long "Mute channel" silences a channel for
+    pub mute_hours: u64,
+    // How long before a premiere or stream starts to remind about it; 0 for no reminders
+    pub reminder_minutes: u64,
+    // Every way notifications get sent; channels can pick a subset by name
+    pub notifiers: Vec<NotifierConfig>,
+    // Print notifications to the terminal when desktop ones can't be shown
+    pub terminal_fallback: bool,
+    // The notification text for each kind of event; channels can override these
+    pub templates: Templates,
+    // When notifications are held back, to be sent together once it's over
+    pub quiet_hours: QuietHours,
+    // Bursts of events sent as one notification, and how many can go out an hour
+    pub coalescing: Coalescing,
+    // A summary of everything found each day or week
+    pub report: ReportConfig
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            thumbnails: false,
+            thumbnail_cache_mb: 50,
+            open_command: String::from("xdg-open"),
+            copy_command: String::from("xclip -selection clipboard"),
+            player_command: String::from("mpv"),
+            player_frontend: None,
+            frontend: None,
+            archive_path: default_archive_path(),
+            mute_hours: 24,
+            reminder_minutes: 30,
+            notifiers: default_notifiers(),
+            terminal_fallback: true,
+            templates: Templates::default(),
+            quiet_hours: QuietHours::default(),
+            coalescing: Coalescing::default(),
+            report: ReportConfig::default()
+        }
+    }
+}
+
+#[cfg(feature = "desktop")]
+fn default_notifiers() -> Vec<NotifierConfig> {
+    vec! [NotifierConfig { name: String::from("desktop"), backend: Backend::Desktop }]
+}
+
+#[cfg(not(feature = "desktop"))]
+fn default_notifiers() -> Vec<NotifierConfig> {
+    vec! [NotifierConfig { name: String::from("terminal"), backend: Backend::Terminal(TerminalConfig { style: TerminalStyle::Color }) }]
+}
+
+// Get the directory the daemon's own files go in
+pub fn state_dir(cfg_path: &Path) -> PathBuf {
+    let mut path = cfg_path.to_path_buf();
+    path.push(STATE_DIR_NAME);
+    path
+}
+
+// Read one of the state files, starting over if it's missing or can't be read; what is
+// what's in it, for the warning
+pub fn load_state<T: DeserializeOwned + Default>(path: &Path, what: &str) -> T {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|_| {
+            eprintln! ("Could not read the {} in {}; starting over", what, path.display());
+            T::default()
+        }),
+        Err(_) => T::default()
+    }
+}
+
+// Write one of the state files
+pub fn save_state<T: Serialize>(path: &Path, state: &T) -> Result<(), ()> {
+    if let Some(dir) = path.parent() { std::fs::create_dir_all(dir).map_err(|_| ())?; }
+    let json_string = serde_json::to_string(state).unwrap();
+
+    // Write next to it and move it over, so a crash never leaves half a file
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, json_string).map_err(|_| ())?;
+    std::fs::rename(&tmp_path, path).map_err(|_| ())
+}
+
+// ~/downloads, or just downloads/ if we can't find home
+fn default_archive_path() -> PathBuf {
+    let mut path = home::home_dir().unwrap_or_default();
+    path.push("downloads");
+    path
+}
+
+impl Config {
+    // Get the path of the config file in the save directory
+    pub fn path(cfg_path: &Path) -> PathBuf {
+        let mut path = cfg_path.to_path_buf();
+        path.push(CONFIG_FILE_NAME);
+        path
+    }
+
+    // Load the config, writing out the defaults the first time around
+    pub fn load(cfg_path: &Path) -> Result<Config, String> {
+        let path = Config::path(cfg_path);
+
+        if !path.exists() {
+            let config = Config::default();
+            let _ = config.save(cfg_path);
+            return Ok(config);
+        }
+
+        let contents = std::fs::read_to_string(&path).map_err(|e| format! ("could not read {}: {}", path.display(), e))?;
+        let config: Config = serde_json::from_str(&contents).map_err(|e| format! ("could not parse {}: {}", path.display(), e))?;
+        config.templates.validate().map_err(|e| format! ("bad {}", e))?;
+        config.quiet_hours.validate().map_err(|e| format! ("bad quiet hours; {}", e))?;
+        config.report.validate().map_err(|e| format! ("bad report; {}", e))?;
+        for instance in config.frontend.iter().chain(config.player_frontend.iter()) {
+            if !instance.starts_with("https://") && !instance.starts_with("http://") {
+                return Err(format! ("front-end \"{}\" needs to be a URL like https://yewtu.be", instance));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn save(&self, cfg_path: &Path) -> Result<(), ()> {
+        let json_string = serde_json::to_string_pretty(&self).unwrap();
+        if std::fs::write(Config::path(cfg_path), json_string).is_err() { Err(()) }
+        else { Ok(()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_files_round_trip_and_start_over_when_broken() {
+        let dir = std::env::temp_dir().join(format! ("yt-notify-state-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = state_dir(&dir).join("numbers.json");
+
+        assert_eq! (load_state::<Vec<u32>>(&path, "numbers"), Vec::<u32>::new());
+        save_state(&path, &vec! [1, 2, 3]).unwrap();
+        assert_eq! (load_state::<Vec<u32>>(&path, "numbers"), vec! [1, 2, 3]);
+        assert! (!path.with_extension("json.tmp").exists());
+
+        std::fs::write(&path, "{ not json").unwrap();
+        assert_eq! (load_state::<Vec<u32>>(&path, "numbers"), Vec::<u32>::new());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,198 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use crate::youtube::Channel;
+use crate::notif::{MessageFormat, ChannelPrefs};
+use crate::templates::Templates;
+
+// Tag written into JSON exports so import can recognise them
+pub const EXPORT_FORMAT_TAG: &str = "yt-notify";
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Opml,
+    Json,
+    Csv
+}
+
+// Everything about a channel worth carrying over to another machine
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExportedChannel {
+    pub name: String,
+    pub channel_id: String,
+    pub url: String,
+    #[serde(default)]
+    pub filter: Vec<String>,
+    #[serde(default)]
+    pub archive: bool,
+    #[serde(default)]
+    pub archive_filter: Option<Vec<String>>,
+    #[serde(default)]
+    pub notifiers: Option<Vec<String>>,
+    #[serde(default)]
+    pub message_format: Option<MessageFormat>,
+    #[serde(default)]
+    pub templates: Templates,
+    #[serde(default)]
+    pub prefs: ChannelPrefs,
+    #[serde(default)]
+    pub break_quiet_hours: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExportDocument {
+    pub format: String,
+    pub version: u32,
+    pub channels: Vec<ExportedChannel>
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Result<ExportFormat, ()> {
+        match name.to_lowercase().as_str() {
+            "opml" => Ok(ExportFormat::Opml),
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => Err(())
+        }
+    }
+}
+
+impl ExportedChannel {
+    pub fn from_channel(channel: &Channel) -> ExportedChannel {
+        ExportedChannel {
+            name: channel.name.clone(),
+            channel_id: channel.channel_id.clone(),
+            url: channel.get_channel_url(),
+            filter: channel.filter.clone(),
+            archive: channel.archive,
+            archive_filter: channel.archive_filter.clone(),
+            notifiers: channel.notifiers.clone(),
+            message_format: channel.message_format,
+            templates: channel.templates.clone(),
+            prefs: channel.prefs.clone(),
+            break_quiet_hours: channel.break_quiet_hours
+        }
+    }
+}
+
+// Write every channel out in the requested format
+pub fn export_channels(channels: &[Channel], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Opml => to_opml(channels),
+        ExportFormat::Json => to_json(channels),
+        ExportFormat::Csv => to_csv(channels)
+    }
+}
+
+// OPML with each channel's Atom feed, so any feed reader can use it
+fn to_opml(channels: &[Channel]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<opml version=\"1.1\">\n");
+    out.push_str("  <head>\n    <title>yt-notify subscriptions</title>\n  </head>\n");
+    out.push_str("  <body>\n");
+    out.push_str("    <outline text=\"YouTube Subscriptions\" title=\"YouTube Subscriptions\">\n");
+    for channel in channels.iter() {
+        out.push_str(&format! ("      <outline text=\"{0}\" title=\"{0}\" type=\"rss\" xmlUrl=\"{1}\" htmlUrl=\"{2}\"/>\n",
+            escape_xml(&channel.name),
+            escape_xml(&channel.get_atom_url()),
+            escape_xml(&channel.get_channel_url())));
+    }
+    out.push_str("    </outline>\n");
+    out.push_str("  </body>\n</opml>\n");
+    out
+}
+
+// A single JSON document that import understands
+fn to_json(channels: &[Channel]) -> String {
+    let doc = ExportDocument {
+        format: String::from(EXPORT_FORMAT_TAG),
+        version: EXPORT_VERSION,
+        channels: channels.iter().map(ExportedChannel::from_channel).collect()
+    };
+    serde_json::to_string_pretty(&doc).unwrap()
+}
+
+// One row per channel; list fields are joined with semicolons
+fn to_csv(channels: &[Channel]) -> String {
+    let mut out = String::from("name,channel_id,url,feed_url,filter,archive,archive_filter\n");
+    for channel in channels.iter() {
+        let row = [
+            escape_csv(&channel.name),
+            escape_csv(&channel.channel_id),
+            escape_csv(&channel.get_channel_url()),
+            escape_csv(&channel.get_atom_url()),
+            escape_csv(&channel.filter.join(";")),
+            String::from(if channel.archive { "true" } else { "false" }),
+            escape_csv(&channel.archive_filter.as_ref().map(|f| f.join(";")).unwrap_or_default())
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn escape_csv(text: &str) -> String {
+    if text.contains(',') || text.contains('"') || text.contains('\n') || text.contains('\r') {
+        format! ("\"{}\"", text.replace('"', "\"\""))
+    } else { String::from(text) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import;
+
+    fn channel(name: &str, id: &str, filter: &[&str], archive: bool, archive_filter: Option<&[&str]>) -> Channel {
+        let mut json = serde_json::json! ({
+            "name": name, "channel_id": id, "channel_type": "Channel", "filter": filter,
+            "path": "/tmp/channel.json", "pic_path": "/tmp/channel.png",
+            "archive": archive, "archive_filter": null, "latest_ids": [null, null]
+        });
+        if let Some(archive_filter) = archive_filter { json["archive_filter"] = serde_json::json! (archive_filter); }
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn json_export_round_trips_through_import() {
+        let mut channels = vec! [
+            channel("Tom Scott", "UCBa659QWEk1AI4Tg--mrJ2A", &[], false, None),
+            channel("A \"quoted\", name", "UCsXVk37bltHxD1rDPwtNM8Q", &["space", "cells"], true, Some(&["live"]))
+        ];
+        channels[1].notifiers = Some(vec! [String::from("phone")]);
+        channels[1].message_format = Some(MessageFormat::Compact);
+        channels[1].templates = serde_json::from_value(serde_json::json! ({ "live": { "summary": "{channel} is on", "body": "{title}" } })).unwrap();
+        channels[1].prefs = serde_json::from_value(serde_json::json! ({ "live": { "urgency": "critical" } })).unwrap();
+        channels[1].break_quiet_hours = true;
+        let imported = import::parse_subscriptions(&export_channels(&channels, ExportFormat::Json)).unwrap();
+
+        assert_eq! (imported.len(), 2);
+        for (original, imported) in channels.iter().zip(imported.iter()) {
+            assert_eq! (imported.name, original.name);
+            assert_eq! (imported.channel_id, original.channel_id);
+            assert_eq! (imported.url, original.get_channel_url());
+            assert_eq! (imported.filter, original.filter);
+            assert_eq! (imported.archive, original.archive);
+            assert_eq! (imported.archive_filter, original.archive_filter);
+            assert_eq! (imported.notifiers, original.notifiers);
+            assert_eq! (imported.message_format, original.message_format);
+            assert_eq! (serde_json::to_value(&imported.templates).unwrap(), serde_json::to_value(&original.templates).unwrap());
+            assert_eq! (serde_json::to_value(&imported.prefs).unwrap(), serde_json::to_value(&original.prefs).unwrap());
+            assert_eq! (imported.break_quiet_hours, original.break_quiet_hours);
+        }
+    }
+
+    #[test]
+    fn csv_escapes_awkward_fields() {
+        let csv = export_channels(&[channel("A \"quoted\", name", "UC1", &["a", "b"], true, None)], ExportFormat::Csv);
+        assert_eq! (csv.lines().nth(1).unwrap(),
+            "\"A \"\"quoted\"\", name\",UC1,https://www.youtube.com/channel/UC1,https://www.youtube.com/feeds/videos.xml?channel_id=UC1,a;b,true,");
+    }
+}
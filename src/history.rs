@@ -0,0 +1,138 @@
+extern crate chrono;
+extern crate serde;
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::{Serialize, Deserialize};
+use crate::config;
+use crate::notif::EventKind;
+use crate::youtube::{Video, Channel};
+use std::io::Write;
+use std::iter::Peekable;
+use std::path::{PathBuf, Path};
+
+// Everything that's been found or archived, one JSON object a line so it only ever gets appended to
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryAction {
+    // The daemon noticed it, whether or not a notification went out
+    Found,
+    // A stream started being archived
+    Archived
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    pub time: i64,
+    pub action: HistoryAction,
+    pub kind: EventKind,
+    pub video_id: String,
+    pub title: String,
+    pub channel_name: String,
+    pub channel_id: String,
+    #[serde(default)]
+    pub duration: Option<u64>
+}
+
+// Which entries the history command shows
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    // Matched against the channel's name, ignoring case
+    pub channel: Option<String>,
+    // Both days included, in local time
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    // Only streams (live, premieres and everything after) if true, only uploads if false
+    pub streams: Option<bool>,
+    // Has to be somewhere in the title, ignoring case
+    pub search: Option<String>,
+    // Open the video of this entry in the list, counting from 1
+    pub open: Option<usize>
+}
+
+impl HistoryEntry {
+    pub fn new(action: HistoryAction, kind: EventKind, vid: &Video, channel: &Channel, time: i64) -> HistoryEntry {
+        HistoryEntry {
+            time,
+            action,
+            kind,
+            video_id: vid.video_id.clone(),
+            title: vid.video_title.clone(),
+            channel_name: channel.name.clone(),
+            channel_id: channel.channel_id.clone(),
+            duration: vid.duration
+        }
+    }
+}
+
+impl HistoryQuery {
+    // Take the history command's options off the front of args, leaving anything else
+    pub fn parse<'a, I: Iterator<Item = &'a String>>(args: &mut Peekable<I>) -> Result<HistoryQuery, String> {
+        let mut query = HistoryQuery::default();
+        while let Some(arg) = args.next_if(|arg| ["--channel", "--since", "--until", "--live", "--uploads", "--search", "--open"].contains(&arg.as_str())) {
+            match arg.as_str() {
+                "--live" => { query.streams = Some(true); },
+                "--uploads" => { query.streams = Some(false); },
+                option => {
+                    let value = args.next().ok_or(format! ("{} needs a value", option))?;
+                    match option {
+                        "--channel" => { query.channel = Some(value.to_lowercase()); },
+                        "--since" => { query.since = Some(parse_date(value)?); },
+                        "--until" => { query.until = Some(parse_date(value)?); },
+                        "--search" => { query.search = Some(value.to_lowercase()); },
+                        _ => { query.open = Some(value.parse().ok().filter(|n| *n > 0).ok_or(format! ("\"{}\" isn't a number in the list", value))?); }
+                    }
+                }
+            }
+        }
+        Ok(query)
+    }
+
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        if let Some(channel) = &self.channel {
+            if !entry.channel_name.to_lowercase().contains(channel.as_str()) { return false; }
+        }
+        if let Some(search) = &self.search {
+            if !entry.title.to_lowercase().contains(search.as_str()) { return false; }
+        }
+        if let Some(streams) = self.streams {
+            if (entry.kind != EventKind::Upload) != streams { return false; }
+        }
+
+        let day = if let Some(time) = Local.timestamp_opt(entry.time, 0).single() { time.date_naive() } else { return false; };
+        self.since.map(|since| day >= since).unwrap_or(true) && self.until.map(|until| day <= until).unwrap_or(true)
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format! ("\"{}\" isn't a date like 2024-03-01", date))
+}
+
+fn history_path(cfg_path: &Path) -> PathBuf {
+    let mut path = config::state_dir(cfg_path);
+    path.push(HISTORY_FILE_NAME);
+    path
+}
+
+// Add an entry to the end of the history
+pub fn record(cfg_path: &Path, entry: &HistoryEntry) -> Result<(), ()> {
+    let path = history_path(cfg_path);
+    if let Some(dir) = path.parent() { std::fs::create_dir_all(dir).map_err(|_| ())?; }
+
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path).map_err(|_| ())?;
+    let line = serde_json::to_string(entry).unwrap();
+    writeln! (file, "{}", line).map_err(|_| ())
+}
+
+// Like record, but only complains if it doesn't work
+pub fn record_or_warn(cfg_path: &Path, entry: &HistoryEntry) {
+    if record(cfg_path, entry).is_err() {
+        eprintln! ("Could not add {} to the history in {}", entry.title, history_path(cfg_path).display());
+    }
+}
+
+// Every entry, oldest first; lines that can't be read (like one cut off by a crash) are skipped
+pub fn load(cfg_path: &Path) -> Vec<HistoryEntry> {
+    let contents = if let Ok(contents) = std::fs::read_to_string(history_path(cfg_path)) { contents } else { return Vec::new(); };
+    contents.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+}
@@ -0,0 +1,196 @@
+extern crate json;
+use std::collections::HashMap;
+use crate::export::{ExportDocument, EXPORT_FORMAT_TAG};
+use crate::notif::{MessageFormat, ChannelPrefs};
+use crate::templates::Templates;
+
+// A subscription found in another app's export
+#[derive(Debug, Clone)]
+pub struct ImportedChannel {
+    pub name: String,
+    pub channel_id: String,
+    pub url: String,
+    pub filter: Vec<String>,
+    pub archive: bool,
+    pub archive_filter: Option<Vec<String>>,
+    pub notifiers: Option<Vec<String>>,
+    pub message_format: Option<MessageFormat>,
+    pub templates: Templates,
+    pub prefs: ChannelPrefs,
+    pub break_quiet_hours: bool
+}
+
+impl ImportedChannel {
+    // Other apps only know the ID, so everything else gets our defaults
+    fn from_id(name: &str, channel_id: &str) -> ImportedChannel {
+        ImportedChannel {
+            name: String::from(name),
+            channel_id: String::from(channel_id),
+            url: format! ("https://www.youtube.com/channel/{}", channel_id),
+            filter: Vec::new(),
+            archive: false,
+            archive_filter: None,
+            notifiers: None,
+            message_format: None,
+            templates: Templates::default(),
+            prefs: ChannelPrefs::default(),
+            break_quiet_hours: false
+        }
+    }
+}
+
+// Work out what kind of export we were given and parse it
+pub fn parse_subscriptions(contents: &str) -> Result<Vec<ImportedChannel>, ()> {
+    if let Ok(parsed) = json::parse(contents) {
+        // Our own export keeps filters, archive settings and notification preferences
+        if parsed["format"].as_str() == Some(EXPORT_FORMAT_TAG) { return parse_export(contents); }
+        // A single JSON document - NewPipe writes one of these. A FreeTube profiles.db with only
+        // the default profile is one line, so it parses too; that has no service_id or url
+        if is_newpipe(&parsed) { return parse_newpipe(&parsed); }
+    }
+
+    // FreeTube's profiles.db is one JSON document per line
+    parse_freetube(contents)
+}
+
+// A JSON document written by export
+fn parse_export(contents: &str) -> Result<Vec<ImportedChannel>, ()> {
+    let doc: ExportDocument = if let Ok(doc) = serde_json::from_str(contents) { doc } else { return Err(()); };
+
+    let mut ret_vec: Vec<ImportedChannel> = Vec::new();
+    for exported in doc.channels.into_iter() {
+        push_unique(&mut ret_vec, ImportedChannel {
+            name: exported.name,
+            channel_id: exported.channel_id,
+            url: exported.url,
+            filter: exported.filter,
+            archive: exported.archive,
+            archive_filter: exported.archive_filter,
+            notifiers: exported.notifiers,
+            message_format: exported.message_format,
+            templates: exported.templates,
+            prefs: exported.prefs,
+            break_quiet_hours: exported.break_quiet_hours
+        });
+    }
+
+    if ret_vec.is_empty() { Err(()) } else { Ok(ret_vec) }
+}
+
+fn is_newpipe(parsed: &json::JsonValue) -> bool {
+    if !parsed["subscriptions"].is_array() { return false; }
+    parsed.has_key("app_version") || parsed["subscriptions"].members().any(|sub| sub.has_key("service_id") || sub.has_key("url"))
+}
+
+// NewPipe's subscriptions.json: {"subscriptions": [{"service_id": 0, "url": ..., "name": ...}]}
+fn parse_newpipe(parsed: &json::JsonValue) -> Result<Vec<ImportedChannel>, ()> {
+    let mut ret_vec: Vec<ImportedChannel> = Vec::new();
+
+    for sub in parsed["subscriptions"].members() {
+        // Service 0 is YouTube; skip SoundCloud, PeerTube and friends
+        if sub["service_id"].as_u32() != Some(0) { continue; }
+
+        let name = if let Some(name) = sub["name"].as_str() { name } else { continue; };
+        let id = if let Some(url) = sub["url"].as_str() { channel_id_from_url(url) } else { None };
+
+        if let Some(channel_id) = id {
+            push_unique(&mut ret_vec, ImportedChannel::from_id(name, &channel_id));
+        }
+    }
+
+    if ret_vec.is_empty() { Err(()) } else { Ok(ret_vec) }
+}
+
+// FreeTube's profiles.db (a NeDB file): {"_id": ..., "name": ..., "subscriptions": [{"id": ..., "name": ...}]}
+fn parse_freetube(contents: &str) -> Result<Vec<ImportedChannel>, ()> {
+    // NeDB only ever appends, so a later line for the same profile replaces the earlier one
+    let mut profiles: Vec<(String, json::JsonValue)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for line in contents.lines() {
+        if line.trim().is_empty() { continue; }
+        let parsed = if let Ok(parsed) = json::parse(line) { parsed } else { return Err(()); };
+
+        let profile_id = String::from(parsed["_id"].as_str().unwrap_or(""));
+        if let Some(&pos) = positions.get(&profile_id) {
+            profiles[pos].1 = parsed;
+        } else {
+            positions.insert(profile_id.clone(), profiles.len());
+            profiles.push((profile_id, parsed));
+        }
+    }
+
+    let mut ret_vec: Vec<ImportedChannel> = Vec::new();
+    for (_, profile) in profiles.iter() {
+        // Deleted profiles are left behind as {"$$deleted": true, "_id": ...}
+        if profile["$$deleted"].as_bool() == Some(true) { continue; }
+
+        for sub in profile["subscriptions"].members() {
+            if let (Some(id), Some(name)) = (sub["id"].as_str(), sub["name"].as_str()) {
+                push_unique(&mut ret_vec, ImportedChannel::from_id(name, id));
+            }
+        }
+    }
+
+    if ret_vec.is_empty() { Err(()) } else { Ok(ret_vec) }
+}
+
+// Pull the UC... ID out of a /channel/ URL
+fn channel_id_from_url(url: &str) -> Option<String> {
+    let split_url: Vec<_> = url.split('/').collect();
+    let pos = split_url.iter().position(|&r| r == "channel")?;
+    split_url.get(pos+1).filter(|id| !id.is_empty()).map(|id| String::from(*id))
+}
+
+// The same channel is usually in several FreeTube profiles; only keep it once
+fn push_unique(channels: &mut Vec<ImportedChannel>, channel: ImportedChannel) {
+    if !channels.iter().any(|c| c.channel_id == channel.channel_id) { channels.push(channel); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(channels: &[ImportedChannel]) -> Vec<&str> {
+        channels.iter().map(|c| c.channel_id.as_str()).collect()
+    }
+
+    #[test]
+    fn newpipe_keeps_only_youtube_channels_once() {
+        let channels = parse_subscriptions(include_str! ("../tests/fixtures/newpipe_subscriptions.json")).unwrap();
+        assert_eq! (ids(&channels), ["UCsXVk37bltHxD1rDPwtNM8Q", "UCBa659QWEk1AI4Tg--mrJ2A"]);
+        // The first name seen wins
+        assert_eq! (channels[0].name, "Kurzgesagt – In a Nutshell");
+        assert_eq! (channels[0].url, "https://www.youtube.com/channel/UCsXVk37bltHxD1rDPwtNM8Q");
+        assert! (channels[0].filter.is_empty() && !channels[0].archive && channels[0].archive_filter.is_none());
+    }
+
+    #[test]
+    fn freetube_uses_the_latest_line_and_skips_deleted_profiles() {
+        let channels = parse_subscriptions(include_str! ("../tests/fixtures/freetube_profiles.db")).unwrap();
+        // Numberphile was only in the deleted profile; Vsauce only in the rewritten one
+        assert_eq! (ids(&channels), ["UCBa659QWEk1AI4Tg--mrJ2A", "UCsXVk37bltHxD1rDPwtNM8Q", "UC6nSFpj9HTCZ5t-N3Rm3-HA"]);
+        assert_eq! (channels[2].name, "Vsauce");
+    }
+
+    #[test]
+    fn freetube_with_only_the_default_profile_is_one_line() {
+        let channels = parse_subscriptions(include_str! ("../tests/fixtures/freetube_single_profile.db")).unwrap();
+        assert_eq! (ids(&channels), ["UCBa659QWEk1AI4Tg--mrJ2A", "UCsXVk37bltHxD1rDPwtNM8Q"]);
+        assert_eq! (channels[1].name, "Kurzgesagt");
+    }
+
+    #[test]
+    fn nothing_to_import_is_an_error() {
+        assert! (parse_subscriptions("{\"subscriptions\": [{\"service_id\": 1, \"url\": \"https://soundcloud.com/x\", \"name\": \"X\"}]}").is_err());
+        assert! (parse_subscriptions("not json at all").is_err());
+        assert! (parse_subscriptions("").is_err());
+    }
+
+    #[test]
+    fn channel_ids_come_from_channel_urls() {
+        assert_eq! (channel_id_from_url("https://www.youtube.com/channel/UC123/videos").as_deref(), Some("UC123"));
+        assert_eq! (channel_id_from_url("https://www.youtube.com/channel/"), None);
+        assert_eq! (channel_id_from_url("https://www.youtube.com/user/someone"), None);
+    }
+}
@@ -0,0 +1,88 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use crate::config;
+use crate::youtube::{Video, Channel};
+use std::path::{PathBuf, Path};
+
+// Videos that have been notified about, and whether they've been watched
+const INBOX_FILE_NAME: &str = "inbox.json";
+// Watched videos are only kept so they don't come back; this many is plenty
+const MAX_WATCHED: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InboxCommand {
+    List,
+    // Counting from 1, in the order List shows them
+    Open(usize),
+    Clear,
+    Count
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InboxItem {
+    pub video_id: String,
+    pub title: String,
+    pub channel_name: String,
+    pub added: i64,
+    pub watched: bool
+}
+
+// The daemon and the inbox command both change it, so it's read fresh every time rather than kept around
+pub struct Inbox {
+    path: PathBuf,
+    items: Vec<InboxItem>
+}
+
+impl Inbox {
+    pub fn load(cfg_path: &Path) -> Inbox {
+        let mut path = config::state_dir(cfg_path);
+        path.push(INBOX_FILE_NAME);
+
+        let items = config::load_state(&path, "inbox");
+        Inbox { path, items }
+    }
+
+    // Put a video in the inbox, unless it's been there before
+    pub fn add(&mut self, vid: &Video, channel: &Channel, now: i64) -> Result<(), ()> {
+        if self.items.iter().any(|item| item.video_id == vid.video_id) { return Ok(()); }
+        self.items.push(InboxItem {
+            video_id: vid.video_id.clone(),
+            title: vid.video_title.clone(),
+            channel_name: channel.name.clone(),
+            added: now,
+            watched: false
+        });
+        self.save()
+    }
+
+    // Oldest first
+    pub fn unwatched(&self) -> Vec<&InboxItem> {
+        self.items.iter().filter(|item| !item.watched).collect()
+    }
+
+    pub fn mark_watched(&mut self, video_id: &str) -> Result<(), ()> {
+        for item in self.items.iter_mut().filter(|item| item.video_id == video_id) { item.watched = true; }
+        self.save()
+    }
+
+    // Mark everything watched
+    pub fn clear(&mut self) -> Result<(), ()> {
+        for item in self.items.iter_mut() { item.watched = true; }
+        self.save()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn save(&mut self) -> Result<(), ()> {
+        // Forget the oldest watched videos once there are too many
+        let watched = self.items.iter().filter(|item| item.watched).count();
+        let mut excess = watched.saturating_sub(MAX_WATCHED);
+        self.items.retain(|item| {
+            if excess > 0 && item.watched { excess -= 1; false } else { true }
+        });
+
+        config::save_state(&self.path, &self.items)
+    }
+}
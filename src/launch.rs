@@ -0,0 +1,14 @@
+use std::process::{Command, Stdio};
+
+// Run the configured opener (a browser, mpv, ...) with the URL as its last argument
+pub fn open_url(command: &str, url: &str) -> Result<(), ()> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or(())?;
+
+    Command::new(program).args(parts).arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|_| ())
+}
@@ -2,27 +2,67 @@ extern crate home;
 extern crate tokio;
 mod youtube;
 mod notif;
-use std::path::{PathBuf};
+mod import;
+mod export;
+mod avatar;
+mod config;
+mod thumbnail;
+mod templates;
+mod quiet;
+mod coalesce;
+mod streams;
+mod history;
+mod inbox;
+mod launch;
+mod report;
+#[cfg(feature = "desktop")]
+mod actions;
+use std::path::{PathBuf, Path};
 use std::io::Write;
+use chrono::TimeZone;
 use youtube::{Channel, Video};
-use notif::{Notif, NotifPrefs};
+use notif::{Notifier, NotifEvent, NotifPrefs, NotifTimeout, Urgency, EventKind};
+use history::{HistoryEntry, HistoryAction};
+use config::Config;
 
 #[derive(Debug)]
 enum Intent {
-    AddChannel(Result<youtube::Channel, ()>),
+    AddChannel(Result<Box<youtube::Channel>, ()>),
     RemoveChannel(String),
     EditChannel(String),
     Archive,
+    Import(PathBuf),
+    Export(export::ExportFormat, Option<PathBuf>),
+    History(history::HistoryQuery),
+    Inbox(inbox::InboxCommand),
+    // What to play, and the front-end to play it through
+    Play(String, Option<String>),
     StartDaemon,
     DumpEntries
 }
 
 fn main() {
     let cfg_path = verify_save_dirs();
+    let config = match Config::load(&cfg_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln! ("FATAL: Invalid config; {}", e);
+            std::process::exit(1);
+        }
+    };
     let mut usr_start_daemon = false;
 
-    for current_intent in find_intents(&cfg_path).iter() {
-        println! ("{:?}", current_intent);
+    let intents = match find_intents(&cfg_path) {
+        Ok(intents) => intents,
+        Err(e) => {
+            eprintln! ("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    for current_intent in intents.iter() {
+        // stderr, so it doesn't end up in exports written to stdout
+        eprintln! ("{:?}", current_intent);
         match current_intent {
             Intent::AddChannel(channel_opt) => {
                 if let Ok(channel) = channel_opt {
@@ -30,6 +70,9 @@ fn main() {
                         eprintln! ("Could not write channel to file. Do you have permission?");
                     } else {
                         println! ("Added {} successfully.", channel.name);
+                        if avatar::refresh_avatar(channel).is_err() {
+                            eprintln! ("Could not download the avatar for {}; using the fallback icon", channel.name);
+                        }
                     }
                 } else { eprintln! ("Could not verify that channel. Is the URL correct?"); }
             },
@@ -40,12 +83,16 @@ fn main() {
                 println! ("Eventually, I will edit the channel with id {}", id);
             },
             Intent::Archive => {
-                let p = std::path::Path::new("/home/jake/downloads/").to_path_buf();
                 let rt = tokio::runtime::Runtime::new().unwrap();
-                let start_fn = start_archive_daemon(&cfg_path, &p);
+                let start_fn = start_archive_daemon(&cfg_path, &config.archive_path);
                 rt.block_on(start_fn);
                 
             },
+            Intent::Import(file) => { import_channels(&cfg_path, file); },
+            Intent::Export(format, file) => { export_channels(&cfg_path, *format, file); },
+            Intent::History(query) => { show_history(&cfg_path, &config, query); },
+            Intent::Inbox(command) => { run_inbox(&cfg_path, &config, *command); },
+            Intent::Play(target, frontend) => { play_video(&cfg_path, &config, target, frontend.as_deref().or(config.player_frontend.as_deref()).or(config.frontend.as_deref())); },
             Intent::StartDaemon => { usr_start_daemon = true; },
             Intent::DumpEntries => { 
                 for ch_path in get_saved_entries(&cfg_path).iter() {
@@ -56,13 +103,194 @@ fn main() {
         }
     }
 
-    if usr_start_daemon { println! ("Starting daemon..."); start_daemon(&cfg_path); }
+    if usr_start_daemon { println! ("Starting daemon..."); start_daemon(&cfg_path, &config); }
+}
+
+// Add every channel found in a NewPipe or FreeTube export
+fn import_channels(cfg_path: &Path, file: &Path) {
+    let contents = if let Ok(contents) = std::fs::read_to_string(file) { contents } else {
+        eprintln! ("Could not read {}. Does it exist?", file.display());
+        return;
+    };
+
+    let found = if let Ok(found) = import::parse_subscriptions(&contents) { found } else {
+        eprintln! ("Could not find any subscriptions in {}. Is it a NewPipe, FreeTube or yt-notify export?", file.display());
+        return;
+    };
+
+    println! ("Found {} subscriptions; verifying and saving them...", found.len());
+    for sub in found.iter() {
+        // Don't clobber the settings of channels we already have
+        let existing = cfg_path.join(format! ("{}.json", sub.channel_id));
+        if existing.exists() && Channel::from_file(&existing).is_ok() {
+            println! ("Skipping {}; it's already saved.", sub.name);
+            continue;
+        }
+
+        if let Ok(mut channel) = Channel::new(sub.name.clone(), sub.url.clone(), cfg_path, sub.filter.clone(), sub.archive, sub.archive_filter.clone()) {
+            channel.notifiers = sub.notifiers.clone();
+            channel.message_format = sub.message_format;
+            channel.templates = sub.templates.clone();
+            channel.prefs = sub.prefs.clone();
+            channel.break_quiet_hours = sub.break_quiet_hours;
+            if channel.write_channel_to_file().is_err() {
+                eprintln! ("Could not write channel to file. Do you have permission?");
+            } else {
+                println! ("Added {} successfully.", channel.name);
+                if avatar::refresh_avatar(&channel).is_err() {
+                    eprintln! ("Could not download the avatar for {}; using the fallback icon", channel.name);
+                }
+            }
+        } else { eprintln! ("Could not verify {}; skipping it.", sub.name); }
+    }
+}
+
+// Write all saved channels to a file (or stdout) as OPML, JSON or CSV
+fn export_channels(cfg_path: &Path, format: export::ExportFormat, file: &Option<PathBuf>) {
+    let mut all_channels: Vec<Channel> = Vec::new();
+    for ch_path in get_saved_entries(cfg_path).iter() {
+        if let Ok(ch) = Channel::from_file(ch_path) { all_channels.push(ch); }
+    }
+
+    let exported = export::export_channels(&all_channels, format);
+    if let Some(out_path) = file {
+        if std::fs::write(out_path, exported).is_err() {
+            eprintln! ("Could not write to {}. Do you have permission?", out_path.display());
+        } else {
+            println! ("Exported {} channels to {}.", all_channels.len(), out_path.display());
+        }
+    } else { print! ("{}", exported); }
+}
+
+// List what's been notified, newest first, or open one of them
+fn show_history(cfg_path: &Path, config: &Config, query: &history::HistoryQuery) {
+    let mut entries: Vec<HistoryEntry> = history::load(cfg_path).into_iter()
+        .filter(|entry| entry.action == HistoryAction::Found && query.matches(entry))
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.time));
+
+    if let Some(n) = query.open {
+        let entry = if let Some(entry) = entries.get(n - 1) { entry } else {
+            eprintln! ("There are only {} entries in the history", entries.len());
+            return;
+        };
+        let url = youtube::link_url(&entry.video_id, config.frontend.as_deref());
+        println! ("Opening {} ({})", entry.title, url);
+        if launch::open_url(&config.open_command, &url).is_err() {
+            eprintln! ("Could not run \"{}\" to open {}", config.open_command, url);
+        }
+        return;
+    }
+
+    if entries.is_empty() { println! ("Nothing in the history matches"); }
+    for (i, entry) in entries.iter().enumerate() {
+        let time = chrono::Local.timestamp_opt(entry.time, 0).single()
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let kind = serde_json::to_value(entry.kind).ok().and_then(|kind| kind.as_str().map(String::from)).unwrap_or_default();
+        println! ("{:>4}  {}  {:<8}  {}: {}  {}", i + 1, time, kind, entry.channel_name, entry.title, youtube::link_url(&entry.video_id, config.frontend.as_deref()));
+    }
+}
+
+// Go through the videos waiting to be watched
+fn run_inbox(cfg_path: &Path, config: &Config, command: inbox::InboxCommand) {
+    let mut inbox = inbox::Inbox::load(cfg_path);
+    match command {
+        inbox::InboxCommand::List => {
+            let items = inbox.unwatched();
+            if items.is_empty() { println! ("Nothing new in the inbox"); }
+            for (i, item) in items.iter().enumerate() {
+                println! ("{:>4}  {}: {}  {}", i + 1, item.channel_name, item.title, youtube::link_url(&item.video_id, config.frontend.as_deref()));
+            }
+        },
+        inbox::InboxCommand::Open(n) => {
+            let item = if let Some(item) = inbox.unwatched().get(n - 1) { (*item).clone() } else {
+                eprintln! ("There are only {} videos in the inbox", inbox.unwatched().len());
+                return;
+            };
+            let url = youtube::link_url(&item.video_id, config.frontend.as_deref());
+            println! ("Opening {} ({})", item.title, url);
+            if launch::open_url(&config.open_command, &url).is_err() {
+                eprintln! ("Could not run \"{}\" to open {}", config.open_command, url);
+                return;
+            }
+            if inbox.mark_watched(&item.video_id).is_err() { eprintln! ("Could not save the inbox to {}", inbox.path().display()); }
+        },
+        inbox::InboxCommand::Clear => {
+            if inbox.clear().is_err() { eprintln! ("Could not save the inbox to {}", inbox.path().display()); }
+            else { println! ("Cleared the inbox"); }
+        },
+        // Just the number, for status bars
+        inbox::InboxCommand::Count => { println! ("{}", inbox.unwatched().len()); }
+    }
+}
+
+// Open a video in the player; target is a video id, a number from the inbox, or a channel's name for its latest video
+fn play_video(cfg_path: &Path, config: &Config, target: &str, frontend: Option<&str>) {
+    let mut inbox = inbox::Inbox::load(cfg_path);
+    // Video ids are 11 characters, so anything shorter that's all digits is a number from the inbox
+    let is_index = !target.is_empty() && target.len() < 11 && target.chars().all(|c| c.is_ascii_digit());
+    let from_inbox = target.parse::<usize>().ok().filter(|n| is_index && *n > 0)
+        .and_then(|n| inbox.unwatched().get(n - 1).map(|item| item.video_id.clone()));
+    let channel = get_saved_entries(cfg_path).iter()
+        .filter_map(|ch_path| Channel::from_file(ch_path).ok())
+        .find(|ch| ch.name.eq_ignore_ascii_case(target));
+
+    let video_id = if let Some(video_id) = &from_inbox { video_id.clone() }
+    else if let Some(channel) = channel {
+        if let Ok(video_id) = channel.get_vid_id_from_index(0) { video_id } else {
+            eprintln! ("Could not find the latest video from {}", channel.name);
+            return;
+        }
+    } else if is_index {
+        match inbox.unwatched().len() {
+            0 => eprintln! ("There's nothing in the inbox"),
+            1 => eprintln! ("There's only 1 video in the inbox"),
+            count => eprintln! ("There are only {} videos in the inbox", count)
+        }
+        return;
+    } else { String::from(target) };
+
+    let vid = if let Ok(vid) = youtube::populate_video_from_id(&video_id) { vid } else {
+        eprintln! ("Could not find a video with the id {}", video_id);
+        return;
+    };
+    let url = youtube::link_url(&vid.video_id, frontend);
+
+    println! ("Playing {} ({})", vid.video_title, url);
+    if launch::open_url(&config.player_command, &url).is_err() {
+        eprintln! ("Could not run \"{}\" to play {}", config.player_command, url);
+        return;
+    }
+    if from_inbox.is_some() && inbox.mark_watched(&video_id).is_err() {
+        eprintln! ("Could not save the inbox to {}", inbox.path().display());
+    }
+}
+
+// Everything the daemon keeps track of between checks
+struct DaemonState {
+    notifiers: Vec<Box<dyn Notifier>>,
+    held: quiet::HeldEvents,
+    coalescer: coalesce::Coalescer,
+    streams: streams::StreamTracker,
+    reporter: report::Reporter
 }
 
-fn start_daemon(cfg_path: &PathBuf) {
+fn start_daemon(cfg_path: &Path, config: &Config) {
+    let mut state = DaemonState {
+        notifiers: notif::build_notifiers(config, cfg_path),
+        held: quiet::HeldEvents::load(cfg_path),
+        coalescer: coalesce::Coalescer::load(cfg_path, &config.coalescing),
+        streams: streams::StreamTracker::load(cfg_path),
+        reporter: report::Reporter::load(cfg_path)
+    };
+
     // Update all channels first
     for ch_path in get_saved_entries(cfg_path).iter() {
         if let Ok(ch) = Channel::from_file(ch_path) {
+            if let Err(e) = ch.templates.validate() {
+                eprintln! ("{} has a bad {}", ch.name, e);
+            }
             if let Err(_) = ch.init_update() {
                 eprintln! ("Could not re-initialize channel {}; using latest ids {} and {}",
                     ch.name,
@@ -82,6 +310,9 @@ fn start_daemon(cfg_path: &PathBuf) {
 
         // Go through each channel
         for channel in all_channels.iter() {
+            // Make sure the notification icon is there and not too old
+            avatar::refresh_if_stale(channel);
+
             // found the last notified id?
             let mut found_last_id = false;
             // Got through the 3 latest videos
@@ -101,7 +332,7 @@ fn start_daemon(cfg_path: &PathBuf) {
                             println! ("there's something new (current video id is {}; latest ids are {:?})", that_vid.video_id, channel.get_latest_id(&that_vid.video_id));
                             println! ("on iteration {}; found_last_id is now {}", i, found_last_id);
                             println! ("video id is {}", that_vid.video_id);
-                            notify_video(&that_vid, &channel);
+                            notify_video(&that_vid, &channel, config, cfg_path, &mut state);
                             if i == 0 {
                                 // Get the first 2 ids
                                 let id_1 = String::from(&that_vid.video_id);
@@ -120,6 +351,25 @@ fn start_daemon(cfg_path: &PathBuf) {
             }
         }
 
+        // See whether any premieres or streams have started or finished
+        let now = chrono::Utc::now();
+        for (kind, vid, channel) in state.streams.poll(config.reminder_minutes as i64 * 60, now.timestamp()) {
+            // The channel's settings might have changed since it started being followed
+            let channel = all_channels.iter().find(|ch| ch.channel_id == channel.channel_id).cloned().unwrap_or(channel);
+            let event = NotifEvent::with_kind(kind, &vid, &channel, &default_prefs(), config);
+            deliver(event, config, cfg_path, &mut state, true);
+        }
+
+        // Quiet hours are over; send everything that was held as one summary
+        if !state.held.is_empty() && !config.quiet_hours.is_quiet(now) {
+            let events = state.held.take();
+            state.coalescer.send_digest(&state.notifiers, &format! ("{} notifications from quiet hours", events.len()), &events, now.timestamp());
+        }
+        state.coalescer.flush(&state.notifiers, now.timestamp());
+        state.reporter.run_if_due(&config.report, config.frontend.as_deref(), &state.notifiers, now);
+
+        notif::tick_all(&state.notifiers);
+
         // Wait for the next check
         std::thread::sleep(std::time::Duration::from_secs(15));
     }
@@ -149,13 +399,11 @@ async fn start_archive_daemon(cfg_path: &PathBuf, archive_path: &PathBuf) {
 			if !latest_vid.is_live { continue; }
 			else {
 				// Get what the title will be
-				let date = "";
-				let title = format! ("[{}]{}.mp4", date, latest_vid.video_title);
-				let mut expected_path = archive_path.clone();
-				expected_path.push(title);
+				let expected_path = archive_file_path(archive_path, &latest_vid);
 				
 				if !expected_path.as_path().exists() {
 					// It's not being archived; start it
+                    history::record_or_warn(cfg_path, &HistoryEntry::new(HistoryAction::Archived, EventKind::Live, &latest_vid, channel, chrono::Utc::now().timestamp()));
                     let _ = tokio::task::spawn_blocking(move || {
                         archive_stream(latest_vid.video_id,
                             String::from(expected_path.as_path().to_str().unwrap()))
@@ -169,12 +417,22 @@ async fn start_archive_daemon(cfg_path: &PathBuf, archive_path: &PathBuf) {
 	}
 }
 
+// Get the file a live stream is archived to
+fn archive_file_path(archive_path: &Path, vid: &Video) -> PathBuf {
+    let date = "";
+    let title = format! ("[{}]{}.mp4", date, vid.video_title);
+    let mut expected_path = archive_path.to_path_buf();
+    expected_path.push(title);
+    expected_path
+}
+
 fn archive_stream(vid_id: String, out_file: String) {
     let youtube_dl_output = std::process::Command::new("youtube-dl")
         .arg("-f")
         .arg("best")
         .arg("-g")
-        .arg(format! ("https://www.youtube.com/watch?v={}", vid_id)).output();
+        // Always YouTube itself, even with a front-end set; that's what youtube-dl knows best
+        .arg(youtube::watch_url(&vid_id)).output();
 
     if let Ok(out_bad) = youtube_dl_output {
         if let Ok(out) = std::str::from_utf8(&out_bad.stdout) {
@@ -192,43 +450,110 @@ fn archive_stream(vid_id: String, out_file: String) {
     }
 }
 
-fn notify_video(vid: &Video, channel: &Channel) {
+fn notify_video(vid: &Video, channel: &Channel, config: &Config, cfg_path: &Path, state: &mut DaemonState) {
+    if !channel.passes_filter(vid) { return; }
+
+    // Keep an eye on streams so their notification can follow them going live and ending
+    state.streams.track(vid, channel, config.reminder_minutes as i64 * 60, chrono::Utc::now().timestamp());
+    deliver(NotifEvent::new(vid, channel, &default_prefs(), config), config, cfg_path, state, false);
+}
+
+// What every channel gets unless its prefs say otherwise
+fn default_prefs() -> NotifPrefs {
     let mut prefs = NotifPrefs::new();
-    prefs.timeout(0).urgency(notify_rust::NotificationUrgency::Normal);
+    prefs.timeout(NotifTimeout::Never).urgency(Urgency::Normal);
+    prefs
+}
 
-    let mut notif = Notif::new();
-    notif.video(vid).channel(channel).preferences(&prefs);
+// Send the event unless the channel's muted or it's quiet hours. Updates to a stream's
+// notification replace it rather than adding another, so they aren't coalesced
+fn deliver(event: NotifEvent, config: &Config, cfg_path: &Path, state: &mut DaemonState, update: bool) {
+    let now = chrono::Utc::now();
+    // Everything goes in the history, even if it's not sent
+    history::record_or_warn(cfg_path, &HistoryEntry::new(HistoryAction::Found, event.kind, &event.video, &event.channel, now.timestamp()));
+
+    if event.channel.is_muted() {
+        println! ("{} is muted; not notifying", event.channel.name);
+        // A stream that was notified about before the mute still has to end
+        notif::dispatch_state(&state.notifiers, &event);
+        return;
+    }
 
-    if let Err(e) = notif.build() {
-        eprintln! ("Couldn't notify; {:?}", e);
-    } else {
-        if let Err(e) = notif.exec() {
-            eprintln! ("Couldn't notify; {:?}", e);
-        }
+    // Anything that's notified about can be watched later from the inbox
+    let mut inbox = inbox::Inbox::load(cfg_path);
+    if inbox.add(&event.video, &event.channel, now.timestamp()).is_err() {
+        eprintln! ("Could not add {} to the inbox in {}", event.video.video_title, inbox.path().display());
     }
+
+    if config.quiet_hours.is_quiet(now) && !config.quiet_hours.breaks_through(&event.video, &event.channel) {
+        println! ("Quiet hours; holding the notification for {}", event.video.video_title);
+        state.held.hold(event);
+        return;
+    }
+
+    if update { state.coalescer.send_now(&state.notifiers, &event, now.timestamp()); }
+    else { state.coalescer.push(event, now.timestamp()); }
 }
 
-// Parse command line arguments
-fn find_intents(save_path: &PathBuf) -> std::vec::Vec<Intent> {
+// Parse command line arguments; a usage error if any of them are wrong
+fn find_intents(save_path: &PathBuf) -> Result<Vec<Intent>, String> {
     let all_args = std::env::args().skip(1).collect::<Vec<_>>();
     let mut ret_intents = Vec::new();
+    let mut args = all_args.iter().peekable();
 
     // Go through each command line arg
-    for arg in 0..all_args.len() {
+    while let Some(arg) = args.next() {
         //Check if it's an arg we're looking for
-        match all_args.get(arg).unwrap().as_str() {
+        match arg.as_str() {
             "-s" | "--start-daemon" => { ret_intents.push(Intent::StartDaemon); },
-            "-a" | "--add-channel" => { ret_intents.push(Intent::AddChannel(prompt_channel(save_path))); },
+            "-a" | "--add-channel" => { ret_intents.push(Intent::AddChannel(prompt_channel(save_path).map(Box::new))); },
             "--archive" => { ret_intents.push(Intent::Archive); },
             "-r" | "--remove-channel" => { ret_intents.push(Intent::RemoveChannel(prompt_string("Enter the \x1b[93mID\x1b[0m of the channel you would like to remove:"))); },
             "-e" | "--edit-channel" => { ret_intents.push(Intent::EditChannel(prompt_string("Enter the \x1b[93mID\x1b[0m of the channel you would like to edit:"))); },
             "-d" | "--dump" => { ret_intents.push(Intent::DumpEntries); },
-            _ => {  }
+            "import" | "--import" => {
+                let file = args.next().ok_or("import needs the path of a NewPipe subscriptions.json or FreeTube profiles.db")?;
+                ret_intents.push(Intent::Import(PathBuf::from(file)));
+            },
+            "export" | "--export" => {
+                let format = args.next().map(|f| export::ExportFormat::from_name(f));
+                if let Some(Ok(format)) = format {
+                    ret_intents.push(Intent::Export(format, args.next_if(|a| !a.starts_with('-')).map(PathBuf::from)));
+                } else { return Err(String::from("export needs a format: opml, json or csv")); }
+            },
+            "history" | "--history" => {
+                let query = history::HistoryQuery::parse(&mut args).map_err(|e| format! ("bad history option; {}", e))?;
+                ret_intents.push(Intent::History(query));
+            },
+            "inbox" | "--inbox" => {
+                let command = match args.next_if(|a| ["open", "clear", "--count"].contains(&a.as_str())).map(|a| a.as_str()) {
+                    Some("open") => {
+                        let n = args.next().and_then(|n| n.parse().ok()).filter(|n| *n > 0).ok_or("inbox open needs the number of a video in the inbox")?;
+                        inbox::InboxCommand::Open(n)
+                    },
+                    Some("clear") => inbox::InboxCommand::Clear,
+                    Some(_) => inbox::InboxCommand::Count,
+                    None => inbox::InboxCommand::List
+                };
+                ret_intents.push(Intent::Inbox(command));
+            },
+            "play" | "--play" => {
+                let mut frontend = None;
+                let mut target = None;
+                while let Some(arg) = args.next_if(|a| a.as_str() == "--frontend" || (target.is_none() && !a.starts_with('-'))) {
+                    if arg == "--frontend" { frontend = Some(args.next().ok_or("--frontend needs the URL of an Invidious or Piped instance")?.clone()); }
+                    else { target = Some(arg.clone()); }
+                }
+                let target = target.ok_or("play needs a video id, the number of a video in the inbox, or a channel's name")?;
+                ret_intents.push(Intent::Play(target, frontend));
+            },
+            other => { return Err(format! ("unknown argument \"{}\"", other)); }
         }
     }
 
-    if ret_intents.len() > 0 { ret_intents }
-    else { vec! [Intent::StartDaemon] }
+    // Nothing at all on the command line just starts the daemon
+    if ret_intents.is_empty() { ret_intents.push(Intent::StartDaemon); }
+    Ok(ret_intents)
 }
 
 // Prompt the user for info about a channel, construct and return it
@@ -344,12 +669,14 @@ fn verify_save_dirs() -> PathBuf {
 }
 
 // Get all the entries in the folder
-fn get_saved_entries(entry_path: &PathBuf) -> Vec<PathBuf> {
+fn get_saved_entries(entry_path: &Path) -> Vec<PathBuf> {
     let all_paths = std::fs::read_dir(entry_path).unwrap();
 
     let mut ret_vec: Vec<PathBuf> = Vec::new();
     for current_path_res in all_paths {
         if let Ok(current_path) = current_path_res {
+            // The config sits next to the channels but isn't one
+            if current_path.file_name().to_str() == Some(config::CONFIG_FILE_NAME) { continue; }
             if let Some(ext) = current_path.path().as_path().extension() {
                 if ext.to_str() == Some("json") { ret_vec.push(current_path.path()); }
             }
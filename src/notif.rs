@@ -1,102 +1,444 @@
-extern crate notify_rust;
-use crate::youtube::{Video, Channel};
-use notify_rust::Notification;
+extern crate serde;
+use crate::youtube::{self, Video, Channel};
+use crate::config::Config;
+use crate::{avatar, thumbnail};
+use crate::templates::{Template, TemplateValues, format_duration};
+use serde::{Serialize, Deserialize};
+use chrono::TimeZone;
+use std::path::{PathBuf, Path};
+
+mod http;
+#[cfg(feature = "desktop")]
+mod desktop;
+pub mod webhook;
+pub mod discord;
+pub mod slack;
+pub mod ntfy;
+pub mod gotify;
+pub mod email;
+pub mod telegram;
+pub mod mqtt;
+pub mod command;
+pub mod terminal;
+#[cfg(test)]
+pub mod testing;
+use webhook::{WebhookConfig, WebhookNotifier};
+use discord::{DiscordConfig, DiscordNotifier};
+use slack::{SlackConfig, SlackNotifier};
+use ntfy::{NtfyConfig, NtfyNotifier};
+use gotify::{GotifyConfig, GotifyNotifier};
+use email::{EmailConfig, EmailNotifier};
+use telegram::{TelegramConfig, TelegramNotifier};
+use mqtt::{MqttConfig, MqttNotifier};
+use command::{CommandConfig, CommandNotifier};
+use terminal::{TerminalConfig, TerminalNotifier};
+#[cfg(feature = "desktop")]
+pub use desktop::{DesktopNotifier, ExecError};
 
 #[derive(Debug)]
-pub enum ExecError {
-    EmptyVideo,
-    EmptyChannel,
-    EmptyPreferences
+pub enum NotifyError {
+    #[cfg(feature = "desktop")]
+    Build(ExecError),
+    #[cfg(feature = "desktop")]
+    Desktop(notify_rust::Error),
+    // Couldn't reach the server at all
+    Http(String),
+    // The server answered with something other than a 2xx
+    Status(u16),
+    Email(String),
+    Mqtt(String),
+    Command(String),
+    Terminal(String),
+    // Something the notifier needs wasn't set up
+    Config(String),
+    // The notifier's thread went away before telling us how it went
+    #[cfg(feature = "desktop")]
+    Disconnected
 }
 
-#[derive(Debug, Clone)]
-pub struct NotifPrefs {
-    timeout: Option<u8>,
-    urgency: Option<notify_rust::NotificationUrgency>
+impl std::fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "desktop")]
+            NotifyError::Build(e) => write! (f, "notification was incomplete ({:?})", e),
+            #[cfg(feature = "desktop")]
+            NotifyError::Desktop(e) => write! (f, "desktop notification failed ({})", e),
+            NotifyError::Http(e) => write! (f, "request failed ({})", e),
+            NotifyError::Status(code) => write! (f, "server responded with HTTP {}", code),
+            NotifyError::Email(e) => write! (f, "could not send email ({})", e),
+            NotifyError::Mqtt(e) => write! (f, "could not publish to MQTT ({})", e),
+            NotifyError::Command(e) => write! (f, "hook failed ({})", e),
+            NotifyError::Terminal(e) => write! (f, "could not print ({})", e),
+            NotifyError::Config(e) => write! (f, "not configured properly ({})", e),
+            #[cfg(feature = "desktop")]
+            NotifyError::Disconnected => write! (f, "notifier thread exited early")
+        }
+    }
 }
 
+// What happened to cause the notification
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Upload,
+    Live,
+    // Scheduled but not started yet
+    Premiere,
+    // Something scheduled is about to start
+    Reminder,
+    // A live stream finished
+    Ended
+}
+
+// Everything a notifier needs to know about one notification
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NotifEvent {
+    pub kind: EventKind,
+    pub video: Video,
+    pub channel: Channel,
+    pub prefs: NotifPrefs,
+    // The notification text, with the templates already filled in
+    pub summary: String,
+    pub body: String,
+    // The Invidious or Piped instance its links point to, if any
+    #[serde(default)]
+    pub frontend: Option<String>
+}
+
+// Something that can deliver a NotifEvent to the user
+pub trait Notifier {
+    // The name it was given in the config; channels pick notifiers by this
+    fn name(&self) -> &str;
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError>;
+    // Send a bundle of events as a single notification
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError>;
+    // Called every time round the daemon loop, for notifiers that batch things up
+    fn tick(&self) -> Result<(), NotifyError> { Ok(()) }
+    // Called instead of notify for events that won't be sent, like a muted channel's, so
+    // notifiers that keep state somewhere (MQTT's live_state) don't fall behind
+    fn update_state(&self, _event: &NotifEvent) -> Result<(), NotifyError> { Ok(()) }
+}
+
+// A bundle of events sent together, like everything held back during quiet hours
 #[derive(Debug, Clone)]
-pub struct Notif<'a> {
-    video_field: Option<&'a Video>,
-    channel_field: Option<&'a Channel>,
-    prefs_field: Option<&'a NotifPrefs>
-}
-
-impl<'a> Notif<'a> {
-    pub fn new() -> Notif<'a> {
-        Notif {
-            video_field: None,
-            channel_field: None,
-            prefs_field: None
+pub struct Digest {
+    pub title: String,
+    pub lines: Vec<DigestLine>,
+    // What it was made from, for notifiers that send structured data; empty for reports
+    pub events: Vec<NotifEvent>
+}
+
+// One line of a digest; headings don't link anywhere
+#[derive(Serialize, Debug, Clone)]
+pub struct DigestLine {
+    pub text: String,
+    pub url: Option<String>
+}
+
+impl Digest {
+    // One line per event
+    pub fn from_events(title: &str, events: &[NotifEvent]) -> Digest {
+        Digest {
+            title: String::from(title),
+            lines: events.iter().map(|event| DigestLine { text: format! ("{}: {}", event.body, event.summary), url: Some(event.watch_url()) }).collect(),
+            events: events.to_vec()
         }
     }
 
-    pub fn video(&mut self, vid: &'a Video) -> &mut Notif<'a> {
-        self.video_field = Some(vid);
-        self
+    // For notifiers that only do plain text
+    pub fn text(&self) -> String {
+        let lines: Vec<String> = self.lines.iter().map(|line| match &line.url {
+            Some(url) => format! ("{} {}", line.text, url),
+            None => line.text.clone()
+        }).collect();
+        lines.join("\n")
     }
+}
 
-    pub fn channel(&mut self, chan: &'a Channel) -> &mut Notif<'a> {
-        self.channel_field = Some(chan);
-        self
+// Which notifier to build, and how it's set up
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NotifierConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub backend: Backend
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Backend {
+    Desktop,
+    Webhook(WebhookConfig),
+    Discord(DiscordConfig),
+    Slack(SlackConfig),
+    Ntfy(NtfyConfig),
+    Gotify(GotifyConfig),
+    Email(EmailConfig),
+    Telegram(TelegramConfig),
+    Mqtt(MqttConfig),
+    Command(CommandConfig),
+    Terminal(TerminalConfig)
+}
+
+// How chat messages are laid out
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageFormat {
+    // Title link, channel avatar and the video thumbnail
+    #[default]
+    Rich,
+    // Just one line of text with the link
+    Compact
+}
+
+impl NotifEvent {
+    // The channel's templates win over the config's, which win over the built-in ones;
+    // its prefs are laid over the ones passed in
+    pub fn new(video: &Video, channel: &Channel, prefs: &NotifPrefs, config: &Config) -> NotifEvent {
+        let kind = if video.is_live { EventKind::Live }
+            else if video.is_upcoming { EventKind::Premiere }
+            else { EventKind::Upload };
+        NotifEvent::with_kind(kind, video, channel, prefs, config)
     }
 
-    pub fn preferences(&mut self, pref: &'a NotifPrefs) -> &mut Notif<'a> {
-        self.prefs_field = Some(pref);
-        self
+    pub fn with_kind(kind: EventKind, video: &Video, channel: &Channel, prefs: &NotifPrefs, config: &Config) -> NotifEvent {
+        let template = channel.templates.get(kind).or_else(|| config.templates.get(kind))
+            .cloned()
+            .unwrap_or_else(|| Template::default_for(kind));
+        let values = TemplateValues {
+            title: &video.video_title,
+            channel: &channel.name,
+            duration: video.duration.map(format_duration).unwrap_or_default(),
+            published: video.published
+                .and_then(|time| chrono::Local.timestamp_opt(time, 0).single())
+                .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            url: youtube::link_url(&video.video_id, config.frontend.as_deref()),
+            matched_keyword: channel.matched_keyword(video).unwrap_or_default()
+        };
+        let (summary, body) = template.render(&values);
+
+        NotifEvent {
+            kind,
+            video: video.clone(),
+            channel: channel.clone(),
+            prefs: channel.prefs.apply(prefs, kind),
+            summary,
+            body,
+            frontend: config.frontend.clone()
+        }
+    }
+
+    pub fn watch_url(&self) -> String {
+        youtube::link_url(&self.video.video_id, self.frontend.as_deref())
+    }
+
+    pub fn channel_url(&self) -> String {
+        self.channel.link_url(self.frontend.as_deref())
+    }
+
+    // Images stay on YouTube's servers; only Invidious proxies them, and we can't tell it from Piped
+    pub fn thumbnail_url(&self) -> String {
+        thumbnail::thumbnail_url(&self.video.video_id)
+    }
+
+    // None until the avatar's been downloaded once
+    pub fn avatar_url(&self) -> Option<String> {
+        avatar::avatar_url(&self.channel)
+    }
+
+    // The channel's choice of layout wins over the notifier's
+    pub fn message_format(&self, default: MessageFormat) -> MessageFormat {
+        self.channel.message_format.unwrap_or(default)
     }
+}
 
-    pub fn build(&self) -> Result<(), ExecError> {
-        self.verify_validity()?;
-        Ok(())
+// Build every notifier in the config
+pub fn build_notifiers(config: &Config, cfg_path: &Path) -> Vec<Box<dyn Notifier>> {
+    let mut ret_vec: Vec<Box<dyn Notifier>> = Vec::new();
+    for notifier in config.notifiers.iter() {
+        match &notifier.backend {
+            #[cfg(feature = "desktop")]
+            Backend::Desktop => {
+                ret_vec.push(Box::new(DesktopNotifier::new(&notifier.name, cfg_path, config)));
+            },
+            // Keep old configs working on builds without it, as long as there's somewhere else to send things
+            #[cfg(not(feature = "desktop"))]
+            Backend::Desktop => {
+                eprintln! ("{} is a desktop notifier, but this build doesn't support them; remove it from {}",
+                    notifier.name, cfg_path.join(crate::config::CONFIG_FILE_NAME).display());
+                if config.terminal_fallback { ret_vec.push(Box::new(TerminalNotifier::fallback())); }
+            },
+            Backend::Webhook(webhook_config) => {
+                ret_vec.push(Box::new(WebhookNotifier::new(&notifier.name, webhook_config)));
+            },
+            Backend::Discord(discord_config) => {
+                ret_vec.push(Box::new(DiscordNotifier::new(&notifier.name, discord_config)));
+            },
+            Backend::Slack(slack_config) => {
+                ret_vec.push(Box::new(SlackNotifier::new(&notifier.name, slack_config)));
+            },
+            Backend::Ntfy(ntfy_config) => {
+                ret_vec.push(Box::new(NtfyNotifier::new(&notifier.name, ntfy_config)));
+            },
+            Backend::Gotify(gotify_config) => {
+                ret_vec.push(Box::new(GotifyNotifier::new(&notifier.name, gotify_config)));
+            },
+            Backend::Email(email_config) => {
+                ret_vec.push(Box::new(EmailNotifier::new(&notifier.name, cfg_path, email_config)));
+            },
+            Backend::Telegram(telegram_config) => {
+                ret_vec.push(Box::new(TelegramNotifier::new(&notifier.name, telegram_config)));
+            },
+            Backend::Mqtt(mqtt_config) => {
+                ret_vec.push(Box::new(MqttNotifier::new(&notifier.name, mqtt_config)));
+            },
+            Backend::Command(command_config) => {
+                ret_vec.push(Box::new(CommandNotifier::new(&notifier.name, command_config)));
+            },
+            Backend::Terminal(terminal_config) => {
+                ret_vec.push(Box::new(TerminalNotifier::new(&notifier.name, terminal_config)));
+            }
+        }
     }
+    ret_vec
+}
 
-    pub fn exec(&self) -> Result<(), notify_rust::Error>{
-        let channel = self.channel_field.unwrap();
-        let video = self.video_field.unwrap();
-        let prefs = self.prefs_field.unwrap();
+// Send the event through every notifier the channel uses; one failing doesn't stop the rest
+pub fn dispatch(notifiers: &[Box<dyn Notifier>], event: &NotifEvent) {
+    for notifier in notifiers.iter() {
+        if !event.channel.uses_notifier(notifier.name()) { continue; }
+        if let Err(e) = notifier.notify(event) {
+            eprintln! ("Couldn't notify through {}; {}", notifier.name(), e);
+        }
+    }
+}
 
-        if channel.passes_filter(video) {
-            let summary = video.video_title.as_str();
-            let body = if video.is_live { format! ("{} is live", channel.name) } else { format! ("{} has uploaded a video", channel.name) };
-            let icon = std::path::PathBuf::from(&channel.pic_path);
-            let timeout = notify_rust::Timeout::Milliseconds(prefs.timeout.unwrap() as u32 * 1000);
-            let urgency = prefs.urgency.unwrap();
+// Let every notifier the channel uses know about an event that isn't being sent
+pub fn dispatch_state(notifiers: &[Box<dyn Notifier>], event: &NotifEvent) {
+    for notifier in notifiers.iter() {
+        if !event.channel.uses_notifier(notifier.name()) { continue; }
+        if let Err(e) = notifier.update_state(event) {
+            eprintln! ("Couldn't update {}; {}", notifier.name(), e);
+        }
+    }
+}
 
-            Notification::new()
-                .summary(summary)
-                .body(body.as_str())
-                .icon(icon.to_str().unwrap())
-                .timeout(timeout)
-                .urgency(urgency)
-                .show()?;
-            Ok(())
-        } else { Ok(())}
+// Send the events as one digest through every notifier, each getting the events for channels that use it
+pub fn dispatch_digest(notifiers: &[Box<dyn Notifier>], title: &str, events: &[NotifEvent]) {
+    for notifier in notifiers.iter() {
+        let events: Vec<NotifEvent> = events.iter().filter(|event| event.channel.uses_notifier(notifier.name())).cloned().collect();
+        if events.is_empty() { continue; }
+        if let Err(e) = notifier.notify_digest(&Digest::from_events(title, &events)) {
+            eprintln! ("Couldn't notify through {}; {}", notifier.name(), e);
+        }
     }
+}
 
-    fn verify_validity(&self) -> Result<(), ExecError> {
-        if let None = self.video_field { Err(ExecError::EmptyVideo) }
-        else if let None = self.channel_field { Err(ExecError::EmptyChannel) }
-        else if let None = self.prefs_field { Err(ExecError::EmptyPreferences) }
-        else { Ok(()) }
+// Send a digest that isn't about any one channel, like a report; to every notifier if names is None
+pub fn dispatch_report(notifiers: &[Box<dyn Notifier>], digest: &Digest, names: Option<&[String]>) {
+    for notifier in notifiers.iter() {
+        if let Some(names) = names { if !names.iter().any(|name| name == notifier.name()) { continue; } }
+        if let Err(e) = notifier.notify_digest(digest) {
+            eprintln! ("Couldn't notify through {}; {}", notifier.name(), e);
+        }
     }
 }
 
+// Let every notifier do its periodic work
+pub fn tick_all(notifiers: &[Box<dyn Notifier>]) {
+    for notifier in notifiers.iter() {
+        if let Err(e) = notifier.tick() {
+            eprintln! ("Couldn't notify through {}; {}", notifier.name(), e);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    Normal,
+    Critical
+}
+
+// How long a notification stays up
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifTimeout {
+    // Whatever the notification server does by default
+    Default,
+    // Until it's dismissed
+    Never,
+    Seconds(u32)
+}
+
+// Anything left as None is taken from whatever's underneath when merged
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct NotifPrefs {
+    timeout: Option<NotifTimeout>,
+    urgency: Option<Urgency>,
+    // Played when the notification shows up, if the server supports it
+    sound: Option<PathBuf>,
+    // Used instead of the channel's avatar
+    icon: Option<PathBuf>,
+    // A freedesktop category hint, e.g. "im.received"
+    category: Option<String>
+}
+
 impl NotifPrefs {
     pub fn new() -> NotifPrefs {
-        NotifPrefs {
-            timeout: None,
-            urgency: None
-        }
+        NotifPrefs::default()
     }
 
-    pub fn timeout(&mut self, time: u8) -> &mut NotifPrefs {
+    pub fn timeout(&mut self, time: NotifTimeout) -> &mut NotifPrefs {
         self.timeout = Some(time);
         self
     }
 
-    pub fn urgency(&mut self, urg: notify_rust::NotificationUrgency) -> &mut NotifPrefs {
+    pub fn urgency(&mut self, urg: Urgency) -> &mut NotifPrefs {
         self.urgency = Some(urg);
         self
     }
-}
\ No newline at end of file
+
+    pub fn get_urgency(&self) -> Urgency {
+        self.urgency.unwrap_or(Urgency::Normal)
+    }
+
+    // Copy of these prefs with anything set in other laid over the top
+    pub fn merged(&self, other: &NotifPrefs) -> NotifPrefs {
+        NotifPrefs {
+            timeout: other.timeout.or(self.timeout),
+            urgency: other.urgency.or(self.urgency),
+            sound: other.sound.clone().or_else(|| self.sound.clone()),
+            icon: other.icon.clone().or_else(|| self.icon.clone()),
+            category: other.category.clone().or_else(|| self.category.clone())
+        }
+    }
+}
+
+// A channel's prefs; the ones for the kind of event win over the ones for all of them
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ChannelPrefs {
+    pub all: NotifPrefs,
+    pub upload: NotifPrefs,
+    pub live: NotifPrefs,
+    pub premiere: NotifPrefs,
+    pub reminder: NotifPrefs,
+    pub ended: NotifPrefs
+}
+
+impl ChannelPrefs {
+    // Lay the channel's prefs for this kind of event over the daemon's
+    pub fn apply(&self, base: &NotifPrefs, kind: EventKind) -> NotifPrefs {
+        let for_kind = match kind {
+            EventKind::Upload => &self.upload,
+            EventKind::Live => &self.live,
+            EventKind::Premiere => &self.premiere,
+            EventKind::Reminder => &self.reminder,
+            EventKind::Ended => &self.ended
+        };
+        base.merged(&self.all).merged(for_kind)
+    }
+}
@@ -0,0 +1,122 @@
+// Runs a command for every event. It gets the details as YTN_* environment variables
+// (YTN_EVENT, YTN_VIDEO_ID, YTN_TITLE, YTN_CHANNEL, YTN_CHANNEL_ID, YTN_IS_LIVE, YTN_URL)
+// and the same JSON the webhook notifier sends on stdin. Digests get YTN_EVENT=digest,
+// YTN_TITLE and YTN_COUNT, and the webhook's digest JSON.
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use super::{Notifier, NotifEvent, NotifyError, Digest};
+use super::webhook::{WebhookPayload, DigestPayload};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommandConfig {
+    // Run through the shell, so pipes and && work
+    pub command: String,
+    // Kill it if it's still going after this long
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: u64
+}
+
+pub struct CommandNotifier {
+    name: String,
+    config: CommandConfig
+}
+
+fn default_timeout() -> u64 { 30 }
+
+impl CommandNotifier {
+    pub fn new(name: &str, config: &CommandConfig) -> CommandNotifier {
+        CommandNotifier {
+            name: String::from(name),
+            config: config.clone()
+        }
+    }
+
+    fn run(&self, env: &[(&str, String)], payload: &str) -> Result<(), NotifyError> {
+        let mut child = shell_command(&self.config.command)
+            .envs(env.iter().cloned())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| NotifyError::Command(format! ("could not start \"{}\" ({})", self.config.command, e)))?;
+
+        // It's fine if the command never reads stdin
+        if let Some(mut stdin) = child.stdin.take() { let _ = stdin.write_all(payload.as_bytes()); }
+
+        // Read stderr on the side so a chatty command can't fill the pipe and hang
+        let mut stderr_pipe = child.stderr.take();
+        let stderr_reader = std::thread::spawn(move || {
+            let mut stderr = String::new();
+            if let Some(pipe) = stderr_pipe.as_mut() { let _ = pipe.read_to_string(&mut stderr); }
+            stderr
+        });
+
+        let started = Instant::now();
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if started.elapsed() >= timeout => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(NotifyError::Command(format! ("\"{}\" timed out after {}s", self.config.command, self.config.timeout_secs)));
+                },
+                Ok(None) => std::thread::sleep(Duration::from_millis(100)),
+                Err(e) => return Err(NotifyError::Command(e.to_string()))
+            }
+        };
+
+        let stderr = stderr_reader.join().unwrap_or_default();
+        if status.success() {
+            if !stderr.trim().is_empty() { eprintln! ("{} (stderr): {}", self.name, stderr.trim()); }
+            Ok(())
+        } else {
+            Err(NotifyError::Command(format! ("\"{}\" exited with {}: {}", self.config.command, status, stderr.trim())))
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+impl Notifier for CommandNotifier {
+    fn name(&self) -> &str { &self.name }
+
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        let payload = serde_json::to_string(&WebhookPayload::from_event(event)).unwrap();
+        let env = [
+            ("YTN_EVENT", String::from(serde_json::to_value(event.kind).unwrap().as_str().unwrap())),
+            ("YTN_VIDEO_ID", event.video.video_id.clone()),
+            ("YTN_TITLE", event.video.video_title.clone()),
+            ("YTN_CHANNEL", event.channel.name.clone()),
+            ("YTN_CHANNEL_ID", event.channel.channel_id.clone()),
+            ("YTN_IS_LIVE", String::from(if event.video.is_live { "1" } else { "0" })),
+            ("YTN_URL", event.watch_url())
+        ];
+        self.run(&env, &payload)
+    }
+
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        let payload = serde_json::to_string(&DigestPayload::from_digest(digest)).unwrap();
+        let env = [
+            ("YTN_EVENT", String::from("digest")),
+            ("YTN_TITLE", digest.title.clone()),
+            ("YTN_COUNT", digest.events.len().to_string())
+        ];
+        self.run(&env, &payload)
+    }
+}
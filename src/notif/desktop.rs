@@ -0,0 +1,268 @@
+extern crate notify_rust;
+use crate::youtube::{Video, Channel};
+use crate::actions::{self, NotifAction};
+use crate::config::Config;
+use crate::thumbnail;
+use super::{Notifier, NotifEvent, NotifyError, NotifPrefs, NotifTimeout, Urgency, EventKind, Digest};
+use super::terminal::TerminalNotifier;
+use notify_rust::{Notification, NotificationHandle};
+#[cfg(all(unix, not(target_os = "macos")))]
+use notify_rust::NotificationHint as Hint;
+use std::collections::HashMap;
+use std::path::{PathBuf, Path};
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug)]
+pub enum ExecError {
+    EmptyVideo,
+    EmptyChannel,
+    EmptyPreferences,
+    EmptyText
+}
+
+// Pops up a notification on the desktop
+pub struct DesktopNotifier {
+    name: String,
+    cfg_path: PathBuf,
+    config: Config,
+    // Used when there's no notification server to talk to
+    fallback: Option<TerminalNotifier>,
+    // The notification showing for each stream, so the next stage can replace it, and which
+    // show() put it up, so buttons on a replaced one don't fire twice
+    shown: Arc<Mutex<HashMap<String, (u32, u64)>>>,
+    next_show: AtomicU64
+}
+
+#[derive(Debug, Clone)]
+pub struct Notif<'a> {
+    video_field: Option<&'a Video>,
+    channel_field: Option<&'a Channel>,
+    prefs_field: Option<&'a NotifPrefs>,
+    text_field: Option<(&'a str, &'a str)>,
+    replaces_field: Option<u32>,
+    image_field: Option<&'a std::path::Path>,
+    actions_field: Option<&'a [NotifAction]>
+}
+
+impl<'a> Notif<'a> {
+    pub fn new() -> Notif<'a> {
+        Notif {
+            video_field: None,
+            channel_field: None,
+            prefs_field: None,
+            text_field: None,
+            replaces_field: None,
+            image_field: None,
+            actions_field: None
+        }
+    }
+
+    pub fn video(&mut self, vid: &'a Video) -> &mut Notif<'a> {
+        self.video_field = Some(vid);
+        self
+    }
+
+    pub fn channel(&mut self, chan: &'a Channel) -> &mut Notif<'a> {
+        self.channel_field = Some(chan);
+        self
+    }
+
+    pub fn preferences(&mut self, pref: &'a NotifPrefs) -> &mut Notif<'a> {
+        self.prefs_field = Some(pref);
+        self
+    }
+
+    // The already-filled-in templates
+    pub fn text(&mut self, summary: &'a str, body: &'a str) -> &mut Notif<'a> {
+        self.text_field = Some((summary, body));
+        self
+    }
+
+    // Update the notification with this ID instead of showing a new one
+    pub fn replaces(&mut self, id: u32) -> &mut Notif<'a> {
+        self.replaces_field = Some(id);
+        self
+    }
+
+    pub fn image(&mut self, path: &'a std::path::Path) -> &mut Notif<'a> {
+        self.image_field = Some(path);
+        self
+    }
+
+    pub fn actions(&mut self, actions: &'a [NotifAction]) -> &mut Notif<'a> {
+        self.actions_field = Some(actions);
+        self
+    }
+
+    pub fn build(&self) -> Result<(), ExecError> {
+        self.verify_validity()?;
+        Ok(())
+    }
+
+    pub fn exec(&self) -> Result<NotificationHandle, notify_rust::Error>{
+        let channel = self.channel_field.unwrap();
+        let prefs = self.prefs_field.unwrap();
+
+        let (summary, body) = self.text_field.unwrap();
+        let icon = prefs.icon.as_ref().unwrap_or(&channel.pic_path);
+        let timeout = prefs.timeout.map(notify_rust::Timeout::from).unwrap_or(notify_rust::Timeout::Default);
+        let urgency = prefs.get_urgency().into();
+
+        let mut notification = Notification::new();
+        notification.summary(summary)
+            .body(body)
+            .icon(icon.to_str().unwrap())
+            .timeout(timeout)
+            .urgency(urgency);
+
+        // The image, sound and category hints are only part of the freedesktop spec
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            if let Some(image) = self.image_field.and_then(|p| p.to_str()) { notification.image_path(image); }
+            if let Some(sound) = prefs.sound.as_ref().and_then(|p| p.to_str()) { notification.hint(Hint::SoundFile(String::from(sound))); }
+            if let Some(category) = &prefs.category { notification.hint(Hint::Category(category.clone())); }
+        }
+
+        if let Some(id) = self.replaces_field { notification.id(id); }
+
+        for action in self.actions_field.unwrap_or(&[]).iter() {
+            notification.action(action.id(), action.label());
+        }
+
+        notification.show()
+    }
+
+    fn verify_validity(&self) -> Result<(), ExecError> {
+        if let None = self.video_field { Err(ExecError::EmptyVideo) }
+        else if let None = self.channel_field { Err(ExecError::EmptyChannel) }
+        else if let None = self.prefs_field { Err(ExecError::EmptyPreferences) }
+        else if let None = self.text_field { Err(ExecError::EmptyText) }
+        else { Ok(()) }
+    }
+}
+
+impl DesktopNotifier {
+    pub fn new(name: &str, cfg_path: &Path, config: &Config) -> DesktopNotifier {
+        DesktopNotifier {
+            name: String::from(name),
+            cfg_path: cfg_path.to_path_buf(),
+            config: config.clone(),
+            fallback: if config.terminal_fallback { Some(TerminalNotifier::fallback()) } else { None },
+            shown: Arc::new(Mutex::new(HashMap::new())),
+            next_show: AtomicU64::new(0)
+        }
+    }
+
+    fn show(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        // The D-Bus connection can't leave the thread it was made on, and it has to stay
+        // around to hear about button clicks; so show it in its own thread and just wait
+        // long enough to find out whether that worked
+        let event = event.clone();
+        let cfg_path = self.cfg_path.clone();
+        let config = self.config.clone();
+        let (tx, rx) = mpsc::channel();
+
+        // Every part of a premiere's or stream's life shares one notification
+        let stream = event.kind != EventKind::Upload;
+        // Nothing comes after this one, so the stream can be forgotten
+        let last = event.kind == EventKind::Ended;
+        let replaces = if stream { self.shown.lock().unwrap().get(&event.video.video_id).map(|(id, _)| *id) } else { None };
+        let this_show = self.next_show.fetch_add(1, Ordering::Relaxed);
+        let shown = Arc::clone(&self.shown);
+
+        std::thread::spawn(move || {
+            let thumb = if config.thumbnails {
+                thumbnail::cached_thumbnail(&cfg_path, &event.video.video_id, config.thumbnail_cache_mb)
+            } else { None };
+            let actions = NotifAction::available(event.kind);
+
+            let mut notif = Notif::new();
+            notif.video(&event.video).channel(&event.channel).preferences(&event.prefs).text(&event.summary, &event.body).actions(&actions);
+            if let Some(path) = &thumb { notif.image(path); }
+            if let Some(id) = replaces { notif.replaces(id); }
+
+            if let Err(e) = notif.build() {
+                let _ = tx.send(Err(NotifyError::Build(e)));
+                return;
+            }
+
+            match notif.exec() {
+                Ok(handle) => {
+                    if last { shown.lock().unwrap().remove(&event.video.video_id); }
+                    else if stream { shown.lock().unwrap().insert(event.video.video_id.clone(), (handle.id(), this_show)); }
+                    let _ = tx.send(Ok(()));
+                    handle.wait_for_action(|action_id| {
+                        // It's been replaced (or the stream's over); the newer one's thread will deal with it
+                        let replaced = stream && !last && shown.lock().unwrap().get(&event.video.video_id).map(|(_, show)| *show != this_show).unwrap_or(true);
+                        if replaced { return; }
+                        if let Some(action) = NotifAction::from_id(action_id) {
+                            actions::run_action(action, &event, &config, &cfg_path);
+                        }
+                    });
+                },
+                Err(e) => { let _ = tx.send(Err(NotifyError::Desktop(e))); }
+            }
+        });
+
+        rx.recv().unwrap_or(Err(NotifyError::Disconnected))
+    }
+
+    // No buttons to wait on, so this one can just be shown from here
+    fn show_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        let lines: Vec<&str> = digest.lines.iter().map(|line| line.text.as_str()).collect();
+        Notification::new()
+            .summary(&digest.title)
+            .body(&lines.join("\n"))
+            .timeout(notify_rust::Timeout::Never)
+            .show()
+            .map(|_| ())
+            .map_err(NotifyError::Desktop)
+    }
+}
+
+impl Notifier for DesktopNotifier {
+    fn name(&self) -> &str { &self.name }
+
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        let result = self.show(event);
+        match (&result, &self.fallback) {
+            (Err(e), Some(fallback)) => {
+                eprintln! ("Couldn't notify through {}; {}; printing it instead", self.name, e);
+                fallback.notify(event)
+            },
+            _ => result
+        }
+    }
+
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        let result = self.show_digest(digest);
+        match (&result, &self.fallback) {
+            (Err(e), Some(fallback)) => {
+                eprintln! ("Couldn't notify through {}; {}; printing it instead", self.name, e);
+                fallback.notify_digest(digest)
+            },
+            _ => result
+        }
+    }
+}
+
+impl From<Urgency> for notify_rust::NotificationUrgency {
+    fn from(urgency: Urgency) -> notify_rust::NotificationUrgency {
+        match urgency {
+            Urgency::Low => notify_rust::NotificationUrgency::Low,
+            Urgency::Normal => notify_rust::NotificationUrgency::Normal,
+            Urgency::Critical => notify_rust::NotificationUrgency::Critical
+        }
+    }
+}
+
+impl From<NotifTimeout> for notify_rust::Timeout {
+    fn from(timeout: NotifTimeout) -> notify_rust::Timeout {
+        match timeout {
+            NotifTimeout::Default => notify_rust::Timeout::Default,
+            NotifTimeout::Never => notify_rust::Timeout::Never,
+            NotifTimeout::Seconds(secs) => notify_rust::Timeout::Milliseconds(secs.saturating_mul(1000))
+        }
+    }
+}
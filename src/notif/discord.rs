@@ -0,0 +1,135 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use super::{Notifier, NotifEvent, NotifyError, EventKind, MessageFormat, Digest, http};
+use std::collections::HashMap;
+
+// Embed sidebar colours
+const LIVE_COLOUR: u32 = 0xFF0000;
+const UPLOAD_COLOUR: u32 = 0x3EA6FF;
+const MAX_CONTENT_LENGTH: usize = 2000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiscordConfig {
+    // The webhook URL from the channel's Integrations settings
+    pub url: String,
+    // Name the messages are posted under; the webhook's own name if None
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub format: MessageFormat,
+    #[serde(default = "http::default_retries")]
+    pub retries: u32
+}
+
+pub struct DiscordNotifier {
+    name: String,
+    config: DiscordConfig
+}
+
+impl DiscordNotifier {
+    pub fn new(name: &str, config: &DiscordConfig) -> DiscordNotifier {
+        DiscordNotifier {
+            name: String::from(name),
+            config: config.clone()
+        }
+    }
+
+    fn build_payload(&self, event: &NotifEvent) -> serde_json::Value {
+        let live = event.kind == EventKind::Live;
+        let title = escape_markdown(&event.summary);
+        let channel = escape_markdown(&event.channel.name);
+
+        let mut payload = match event.message_format(self.config.format) {
+            MessageFormat::Compact => {
+                let marker = if live { "\u{1F534} **LIVE** " } else { "" };
+                json! ({ "content": format! ("{}[{}](<{}>) - {}", marker, title, event.watch_url(), channel) })
+            },
+            MessageFormat::Rich => {
+                let mut author = json! ({ "name": event.channel.name, "url": event.channel_url() });
+                if let Some(icon) = event.avatar_url() { author["icon_url"] = json! (icon); }
+
+                let mut embed = json! ({
+                    "title": event.summary,
+                    "url": event.watch_url(),
+                    "author": author,
+                    "image": { "url": event.thumbnail_url() },
+                    "color": if live { LIVE_COLOUR } else { UPLOAD_COLOUR }
+                });
+                if live { embed["description"] = json! ("\u{1F534} **LIVE**"); }
+
+                json! ({
+                    "content": escape_markdown(&event.body),
+                    "embeds": [embed]
+                })
+            }
+        };
+
+        if let Some(username) = &self.config.username { payload["username"] = json! (username); }
+        payload
+    }
+
+    // A heading and a line of links; Discord cuts messages off at 2000 characters
+    fn build_digest_payload(&self, digest: &Digest) -> serde_json::Value {
+        let mut content = format! ("**{}**", escape_markdown(&digest.title));
+        for line in digest.lines.iter() {
+            let line = match &line.url {
+                Some(url) => format! ("\n[{}](<{}>)", escape_markdown(&line.text), url),
+                None => format! ("\n**{}**", escape_markdown(&line.text))
+            };
+            if content.chars().count() + line.chars().count() > MAX_CONTENT_LENGTH { break; }
+            content.push_str(&line);
+        }
+
+        let mut payload = json! ({ "content": content });
+        if let Some(username) = &self.config.username { payload["username"] = json! (username); }
+        payload
+    }
+
+    fn post(&self, payload: serde_json::Value) -> Result<(), NotifyError> {
+        let body = payload.to_string();
+        let headers = HashMap::new();
+        http::send_with_retries(self.config.retries, || http::post_json(&self.config.url, &headers, &body))?;
+        Ok(())
+    }
+}
+
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &str { &self.name }
+
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        self.post(self.build_payload(event))
+    }
+
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        self.post(self.build_digest_payload(digest))
+    }
+}
+
+// Stop titles like "*NEW* video" from turning into formatting
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\*_~`|>[]()".contains(c) { escaped.push('\\'); }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notif::testing;
+
+    #[test]
+    fn links_go_to_the_frontend_but_images_stay_on_youtube() {
+        let notifier = DiscordNotifier::new("discord", &DiscordConfig { url: String::new(), username: None, format: MessageFormat::Rich, retries: 0 });
+        let mut event = testing::event(EventKind::Upload);
+        event.frontend = Some(String::from("https://yewtu.be/"));
+
+        let embed = &notifier.build_payload(&event)["embeds"][0];
+        assert_eq! (embed["url"], "https://yewtu.be/watch?v=dQw4w9WgXcQ");
+        assert_eq! (embed["author"]["url"], "https://yewtu.be/channel/UCuAXFkgsw1L7xaCfnd5JJOw");
+        assert_eq! (embed["image"]["url"], "https://i.ytimg.com/vi/dQw4w9WgXcQ/mqdefault.jpg");
+    }
+}
@@ -0,0 +1,230 @@
+extern crate lettre;
+extern crate serde;
+use lettre::{Message, SmtpTransport, Transport};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use serde::{Serialize, Deserialize};
+use super::{Notifier, NotifEvent, NotifyError, Digest};
+use crate::config;
+use std::path::{PathBuf, Path};
+use std::sync::Mutex;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    // Plain SMTP; only really useful for a local test sink
+    None,
+    StartTls,
+    // TLS from the start, usually on port 465
+    Implicit
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailMode {
+    // One email per event
+    Each,
+    // Collect events and send them together every batch_minutes
+    Batched
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmailConfig {
+    pub host: String,
+    // The usual port for the security setting if None
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default = "default_security")]
+    pub security: SmtpSecurity,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    #[serde(default = "default_mode")]
+    pub mode: EmailMode,
+    #[serde(default = "default_batch_minutes")]
+    pub batch_minutes: u64
+}
+
+pub struct EmailNotifier {
+    name: String,
+    config: EmailConfig,
+    batch: Mutex<Batch>,
+    // Where the batch is kept, so a restart doesn't lose it
+    batch_path: PathBuf
+}
+
+// Events waiting for the next batch, and when the last batch went out
+#[derive(Serialize, Deserialize, Default)]
+struct Batch {
+    events: Vec<NotifEvent>,
+    last_sent: Option<i64>
+}
+
+fn default_security() -> SmtpSecurity { SmtpSecurity::StartTls }
+fn default_mode() -> EmailMode { EmailMode::Each }
+fn default_batch_minutes() -> u64 { 60 }
+
+impl EmailNotifier {
+    pub fn new(name: &str, cfg_path: &Path, config: &EmailConfig) -> EmailNotifier {
+        // One file per notifier, named so it's safe whatever the notifier's called
+        let file_name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+        let mut batch_path = config::state_dir(cfg_path);
+        batch_path.push(format! ("email-{}.json", file_name));
+
+        let mut batch: Batch = config::load_state(&batch_path, "email batch");
+        if batch.last_sent.is_none() { batch.last_sent = Some(chrono::Utc::now().timestamp()); }
+        EmailNotifier {
+            name: String::from(name),
+            config: config.clone(),
+            batch: Mutex::new(batch),
+            batch_path
+        }
+    }
+
+    fn save_batch(&self, batch: &Batch) {
+        if config::save_state(&self.batch_path, batch).is_err() {
+            eprintln! ("Could not save the email batch to {}", self.batch_path.display());
+        }
+    }
+
+    fn transport(&self) -> Result<SmtpTransport, NotifyError> {
+        let mut builder = match self.config.security {
+            SmtpSecurity::None => SmtpTransport::builder_dangerous(&self.config.host),
+            SmtpSecurity::StartTls => SmtpTransport::starttls_relay(&self.config.host).map_err(smtp_error)?,
+            SmtpSecurity::Implicit => SmtpTransport::relay(&self.config.host).map_err(smtp_error)?
+        };
+        if let Some(port) = self.config.port { builder = builder.port(port); }
+        if let (Some(username), Some(password)) = (&self.config.username, &self.config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(builder.build())
+    }
+
+    fn send(&self, subject: &str, plain: String, html: String) -> Result<(), NotifyError> {
+        let from: Mailbox = self.config.from.parse().map_err(|e| NotifyError::Email(format! ("bad from address ({})", e)))?;
+        let mut builder = Message::builder().from(from).subject(subject);
+        for to in self.config.to.iter() {
+            let mailbox: Mailbox = to.parse().map_err(|e| NotifyError::Email(format! ("bad to address {} ({})", to, e)))?;
+            builder = builder.to(mailbox);
+        }
+
+        let message = builder.multipart(MultiPart::alternative_plain_html(plain, html))
+            .map_err(|e| NotifyError::Email(e.to_string()))?;
+        self.transport()?.send(&message).map_err(smtp_error)?;
+        Ok(())
+    }
+}
+
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str { &self.name }
+
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        match self.config.mode {
+            EmailMode::Each => {
+                let subject = format! ("{}: {}", event.body, event.summary);
+                let events = std::slice::from_ref(event);
+                self.send(&subject, plain_body(events), html_body(events))
+            },
+            EmailMode::Batched => {
+                let mut batch = self.batch.lock().unwrap();
+                batch.events.push(event.clone());
+                self.save_batch(&batch);
+                Ok(())
+            }
+        }
+    }
+
+    // Digests go out straight away, even when batching
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        self.send(&digest.title, digest.text(), digest_html(digest))
+    }
+
+    // Send off the batch once it's been long enough
+    fn tick(&self) -> Result<(), NotifyError> {
+        let now = chrono::Utc::now().timestamp();
+        let mut batch = self.batch.lock().unwrap();
+        if now - batch.last_sent.unwrap_or(now) < self.config.batch_minutes as i64 * 60 { return Ok(()); }
+        batch.last_sent = Some(now);
+        if batch.events.is_empty() { return Ok(()); }
+
+        let subject = format! ("{} new videos", batch.events.len());
+        let result = self.send(&subject, plain_body(&batch.events), html_body(&batch.events));
+        // Hang on to them for the next try if it didn't go through
+        if result.is_ok() { batch.events.clear(); }
+        self.save_batch(&batch);
+        result
+    }
+}
+
+fn smtp_error(e: lettre::transport::smtp::Error) -> NotifyError {
+    NotifyError::Email(e.to_string())
+}
+
+fn plain_body(events: &[NotifEvent]) -> String {
+    let mut body = String::new();
+    for event in events.iter() {
+        body.push_str(&format! ("{}\n{}\n{}\n\n", event.body, event.summary, event.watch_url()));
+    }
+    body
+}
+
+fn html_body(events: &[NotifEvent]) -> String {
+    let mut body = String::from("<html><body><ul>\n");
+    for event in events.iter() {
+        body.push_str(&format! ("<li>{}: <a href=\"{}\">{}</a></li>\n",
+            escape_html(&event.body),
+            escape_html(&event.watch_url()),
+            escape_html(&event.summary)));
+    }
+    body.push_str("</ul></body></html>\n");
+    body
+}
+
+fn digest_html(digest: &Digest) -> String {
+    let mut body = format! ("<html><body><h3>{}</h3><ul>\n", escape_html(&digest.title));
+    for line in digest.lines.iter() {
+        match &line.url {
+            Some(url) => body.push_str(&format! ("<li><a href=\"{}\">{}</a></li>\n", escape_html(url), escape_html(&line.text))),
+            None => body.push_str(&format! ("</ul><h4>{}</h4><ul>\n", escape_html(&line.text)))
+        }
+    }
+    body.push_str("</ul></body></html>\n");
+    body
+}
+
+// Also used for the HTML reports
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notif::{testing, EventKind};
+
+    #[test]
+    fn batches_survive_a_restart() {
+        let dir = std::env::temp_dir().join(format! ("yt-notify-email-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config: EmailConfig = serde_json::from_value(serde_json::json! ({
+            "host": "127.0.0.1", "from": "ytn@example.com", "to": ["me@example.com"], "mode": "batched"
+        })).unwrap();
+
+        let notifier = EmailNotifier::new("daily mail", &dir, &config);
+        notifier.notify(&testing::event(EventKind::Upload)).unwrap();
+        let last_sent = notifier.batch.lock().unwrap().last_sent;
+
+        let batch = EmailNotifier::new("daily mail", &dir, &config).batch.into_inner().unwrap();
+        assert_eq! (batch.events.len(), 1);
+        assert_eq! (batch.events[0].video.video_id, "dQw4w9WgXcQ");
+        // The hour keeps counting from before the restart
+        assert_eq! (batch.last_sent, last_sent);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
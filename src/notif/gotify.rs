@@ -0,0 +1,99 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use super::{Urgency, Notifier, NotifEvent, NotifyError, Digest, http};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GotifyConfig {
+    // Base URL of the Gotify server, e.g. https://push.example.com
+    pub server: String,
+    // An application token
+    pub token: String,
+    #[serde(default = "http::default_retries")]
+    pub retries: u32
+}
+
+pub struct GotifyNotifier {
+    name: String,
+    config: GotifyConfig
+}
+
+// The Gotify Android app only makes noise from 4 up, and pops up over everything from 8
+fn priority(urgency: Urgency) -> u8 {
+    match urgency {
+        Urgency::Low => 2,
+        Urgency::Normal => 5,
+        Urgency::Critical => 8
+    }
+}
+
+impl GotifyNotifier {
+    pub fn new(name: &str, config: &GotifyConfig) -> GotifyNotifier {
+        GotifyNotifier {
+            name: String::from(name),
+            config: config.clone()
+        }
+    }
+
+    fn send(&self, body: serde_json::Value) -> Result<(), NotifyError> {
+        let body = body.to_string();
+        let mut headers = HashMap::new();
+        headers.insert(String::from("X-Gotify-Key"), self.config.token.clone());
+
+        let url = format! ("{}/message", self.config.server.trim_end_matches('/'));
+        http::send_with_retries(self.config.retries, || http::post_json(&url, &headers, &body))?;
+        Ok(())
+    }
+}
+
+impl Notifier for GotifyNotifier {
+    fn name(&self) -> &str { &self.name }
+
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        self.send(json! ({
+            "title": event.body,
+            "message": format! ("{}\n{}", event.summary, event.watch_url()),
+            "priority": priority(event.prefs.get_urgency()),
+            "extras": {
+                "client::notification": { "click": { "url": event.watch_url() } }
+            }
+        }))
+    }
+
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        self.send(json! ({
+            "title": digest.title,
+            "message": digest.text(),
+            "priority": priority(Urgency::Normal)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notif::{testing, EventKind};
+
+    #[test]
+    fn events_are_sent_as_messages() {
+        let (url, requests) = testing::serve(vec! [(200, vec! [])]);
+        let notifier = GotifyNotifier::new("gotify", &GotifyConfig {
+            server: format! ("{}/", url.trim_end_matches("/hook")),
+            token: String::from("AppToken"),
+            retries: 0
+        });
+        let mut event = testing::event(EventKind::Upload);
+        event.prefs.urgency(Urgency::Low);
+        notifier.notify(&event).unwrap();
+
+        let request = requests.recv().unwrap();
+        assert_eq! (request.path, "/message");
+        assert_eq! (request.headers["x-gotify-key"], "AppToken");
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq! (body["title"], event.body);
+        assert_eq! (body["message"], format! ("{}\nhttps://www.youtube.com/watch?v=dQw4w9WgXcQ", event.summary));
+        assert_eq! (body["priority"], 2);
+        assert_eq! (body["extras"]["client::notification"]["click"]["url"], "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+    }
+}
@@ -0,0 +1,119 @@
+extern crate easy_http_request;
+use easy_http_request::{DefaultHttpRequest, HttpRequestBody, HttpResponse};
+use easy_http_request::mime;
+use super::NotifyError;
+use std::collections::HashMap;
+use std::time::Duration;
+
+// How long to wait before the first retry; it doubles after every failure
+#[cfg(not(test))]
+const FIRST_BACKOFF: Duration = Duration::from_secs(1);
+#[cfg(test)]
+const FIRST_BACKOFF: Duration = Duration::from_millis(20);
+// Don't let a rate limit hold up the daemon for longer than this
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+// How many times the HTTP notifiers try again after the first attempt fails, unless they're told otherwise
+pub fn default_retries() -> u32 { 3 }
+
+// POST a JSON body with the given extra headers
+pub fn post_json(url: &str, headers: &HashMap<String, String>, body: &str) -> Result<HttpResponse, NotifyError> {
+    let mut request = DefaultHttpRequest::post_from_url_str(url).map_err(|e| NotifyError::Http(format! ("{:?}", e)))?;
+    request.body = Some(HttpRequestBody::Text {
+        content_type: mime::APPLICATION_JSON,
+        body: String::from(body)
+    });
+    if !headers.is_empty() { request.headers = Some(headers.clone()); }
+
+    request.send().map_err(|e| NotifyError::Http(format! ("{:?}", e)))
+}
+
+// Keep calling send until it gets a 2xx, backing off between tries (or for as long as
+// a 429's Retry-After says). Other client errors won't get any better, so those give up straight away.
+pub fn send_with_retries<F>(retries: u32, send: F) -> Result<HttpResponse, NotifyError>
+    where F: Fn() -> Result<HttpResponse, NotifyError>
+{
+    let mut backoff = FIRST_BACKOFF;
+    let mut attempt = 0;
+
+    loop {
+        let result = send();
+        let retryable = match &result {
+            Ok(response) if (200..300).contains(&response.status_code) => { return result; },
+            Ok(response) => response.status_code == 429 || response.status_code >= 500,
+            Err(_) => true
+        };
+
+        if !retryable || attempt >= retries {
+            return match result {
+                Ok(response) => Err(NotifyError::Status(response.status_code)),
+                Err(e) => Err(e)
+            };
+        }
+
+        let rate_limit = result.as_ref().ok().and_then(retry_after);
+        std::thread::sleep(rate_limit.unwrap_or(backoff));
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+// How long a 429 asked us to wait; Retry-After is in seconds (Discord sends fractions)
+fn retry_after(response: &HttpResponse) -> Option<Duration> {
+    if response.status_code != 429 { return None; }
+    let secs: f64 = response.headers.get("retry-after")?.trim().parse().ok()?;
+    if secs < 0.0 { return None; }
+    Some(Duration::from_secs_f64(secs).min(MAX_RETRY_AFTER))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notif::testing;
+    use std::time::Instant;
+
+    fn post(url: &str) -> Result<HttpResponse, NotifyError> {
+        post_json(url, &HashMap::new(), "{}")
+    }
+
+    #[test]
+    fn server_errors_are_retried_with_backoff() {
+        let (url, requests) = testing::serve(vec! [(500, vec! []), (503, vec! []), (200, vec! [])]);
+        assert! (send_with_retries(3, || post(&url)).is_ok());
+
+        let times: Vec<Instant> = requests.try_iter().map(|request| request.received).collect();
+        assert_eq! (times.len(), 3);
+        assert! (times[1] - times[0] >= FIRST_BACKOFF);
+        assert! (times[2] - times[1] >= FIRST_BACKOFF * 2);
+    }
+
+    #[test]
+    fn gives_up_after_the_last_retry() {
+        let (url, requests) = testing::serve(vec! [(500, vec! []), (502, vec! []), (500, vec! [])]);
+        assert! (matches! (send_with_retries(2, || post(&url)), Err(NotifyError::Status(500))));
+        assert_eq! (requests.try_iter().count(), 3);
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let (url, requests) = testing::serve(vec! [(404, vec! []), (200, vec! [])]);
+        assert! (matches! (send_with_retries(3, || post(&url)), Err(NotifyError::Status(404))));
+        assert_eq! (requests.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn rate_limits_wait_for_retry_after() {
+        let (url, requests) = testing::serve(vec! [(429, vec! [("Retry-After", "0.5")]), (200, vec! [])]);
+        assert! (send_with_retries(3, || post(&url)).is_ok());
+
+        let times: Vec<Instant> = requests.try_iter().map(|request| request.received).collect();
+        assert_eq! (times.len(), 2);
+        assert! (times[1] - times[0] >= Duration::from_millis(500));
+    }
+
+    #[test]
+    fn unreachable_servers_are_an_error() {
+        // Nothing listens on the discard port
+        assert! (matches! (send_with_retries(0, || post("http://127.0.0.1:9/hook")), Err(NotifyError::Http(_))));
+    }
+}
@@ -0,0 +1,239 @@
+// Publishes each event as JSON to <prefix>/<channel_id>/<event>, e.g. yt-notify/UC.../live.
+// <prefix>/<channel_id>/live_state is also kept as a retained "ON" while the channel is live
+// (and "OFF" once the stream ends), so anything subscribing later (like a Home Assistant
+// binary_sensor) still sees it.
+// Digests go to <prefix>/digest as {"title": ..., "lines": [...], "events": [...]}.
+extern crate rumqttc;
+extern crate serde;
+use rumqttc::{Client, MqttOptions, QoS, Transport};
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use super::{Notifier, NotifEvent, NotifyError, EventKind, Digest};
+use std::time::Duration;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    // 0, 1 or 2
+    #[serde(default = "default_qos")]
+    pub qos: u8,
+    // Connect over TLS, checking the broker against the system's certificates
+    #[serde(default)]
+    pub tls: bool,
+    #[serde(default = "default_prefix")]
+    pub topic_prefix: String
+}
+
+pub struct MqttNotifier {
+    name: String,
+    config: MqttConfig,
+    client: Client
+}
+
+fn default_port() -> u16 { 1883 }
+fn default_client_id() -> String { String::from("yt-notify") }
+fn default_qos() -> u8 { 1 }
+fn default_prefix() -> String { String::from("yt-notify") }
+
+impl MqttNotifier {
+    pub fn new(name: &str, config: &MqttConfig) -> MqttNotifier {
+        let mut options = MqttOptions::new(config.client_id.clone(), config.host.clone(), config.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            options.set_credentials(username.clone(), password.clone());
+        }
+        if config.tls { options.set_transport(Transport::tls_with_default_config()); }
+
+        // The connection only does anything while something's iterating over it;
+        // it reconnects by itself, so errors just get logged
+        let (client, mut connection) = Client::new(options, 16);
+        let thread_name = String::from(name);
+        std::thread::spawn(move || {
+            for notification in connection.iter() {
+                if let Err(e) = notification {
+                    eprintln! ("MQTT connection for {} failed; {}", thread_name, e);
+                    std::thread::sleep(Duration::from_secs(5));
+                }
+            }
+        });
+
+        MqttNotifier {
+            name: String::from(name),
+            config: config.clone(),
+            client
+        }
+    }
+
+    fn qos(&self) -> QoS {
+        match self.config.qos {
+            0 => QoS::AtMostOnce,
+            2 => QoS::ExactlyOnce,
+            _ => QoS::AtLeastOnce
+        }
+    }
+
+    fn publish_live_state(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        let topic = format! ("{}/{}/live_state", self.config.topic_prefix, event.channel.channel_id);
+        match event.kind {
+            EventKind::Live => self.publish(topic, true, String::from("ON")),
+            EventKind::Ended => self.publish(topic, true, String::from("OFF")),
+            _ => Ok(())
+        }
+    }
+
+    // Never waits: while the broker's down nothing takes messages off the queue, so once
+    // it's full they're dropped rather than holding up every other notifier
+    fn publish(&self, topic: String, retain: bool, payload: String) -> Result<(), NotifyError> {
+        self.client.try_publish(topic, self.qos(), retain, payload)
+            .map_err(|_| NotifyError::Mqtt(String::from("too many messages waiting for the broker; is it down?")))
+    }
+}
+
+impl Notifier for MqttNotifier {
+    fn name(&self) -> &str { &self.name }
+
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        let prefix = format! ("{}/{}", self.config.topic_prefix, event.channel.channel_id);
+        let kind = serde_json::to_value(event.kind).unwrap();
+
+        self.publish(format! ("{}/{}", prefix, kind.as_str().unwrap()), false, event_json(event).to_string())?;
+        self.publish_live_state(event)
+    }
+
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        let payload = json! ({
+            "title": digest.title,
+            "lines": digest.lines,
+            "events": digest.events.iter().map(event_json).collect::<Vec<_>>()
+        });
+        self.publish(format! ("{}/digest", self.config.topic_prefix), false, payload.to_string())?;
+
+        // Streams that went live or ended while held back still have to show up in live_state
+        for event in digest.events.iter() { self.publish_live_state(event)?; }
+        Ok(())
+    }
+
+    fn update_state(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        self.publish_live_state(event)
+    }
+}
+
+fn event_json(event: &NotifEvent) -> serde_json::Value {
+    json! ({
+        "event": event.kind,
+        "video_id": event.video.video_id,
+        "title": event.video.video_title,
+        "channel_name": event.channel.name,
+        "channel_id": event.channel.channel_id,
+        "is_live": event.video.is_live,
+        "url": event.watch_url()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notif::testing;
+    use std::time::Instant;
+
+    fn config(port: u16) -> MqttConfig {
+        serde_json::from_value(json! ({ "host": "127.0.0.1", "port": port })).unwrap()
+    }
+
+    // Just enough of a broker to accept a connection and pass on (topic, retained, payload) for each publish
+    fn broker() -> (u16, std::sync::mpsc::Receiver<(String, bool, String)>) {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            loop {
+                let mut header = [0u8; 1];
+                if stream.read_exact(&mut header).is_err() { return; }
+                let (mut length, mut shift) = (0usize, 0);
+                loop {
+                    let mut byte = [0u8; 1];
+                    if stream.read_exact(&mut byte).is_err() { return; }
+                    length |= ((byte[0] & 0x7f) as usize) << shift;
+                    shift += 7;
+                    if byte[0] & 0x80 == 0 { break; }
+                }
+                let mut packet = vec! [0u8; length];
+                if stream.read_exact(&mut packet).is_err() { return; }
+
+                match header[0] >> 4 {
+                    // CONNECT gets a CONNACK
+                    1 => { let _ = stream.write_all(&[0x20, 0x02, 0x00, 0x00]); },
+                    // PUBLISH, at QoS 0 so there's no packet id
+                    3 => {
+                        let topic_length = ((packet[0] as usize) << 8) | packet[1] as usize;
+                        let topic = String::from_utf8(packet[2..2 + topic_length].to_vec()).unwrap();
+                        let payload = String::from_utf8(packet[2 + topic_length..].to_vec()).unwrap();
+                        let _ = tx.send((topic, header[0] & 1 == 1, payload));
+                    },
+                    // PINGREQ gets a PINGRESP
+                    12 => { let _ = stream.write_all(&[0xd0, 0x00]); },
+                    _ => {  }
+                }
+            }
+        });
+        (port, rx)
+    }
+
+    #[test]
+    fn digests_keep_live_state_up_to_date() {
+        let (port, published) = broker();
+        let mut config = config(port);
+        config.qos = 0;
+        let notifier = MqttNotifier::new("mqtt", &config);
+
+        let mut ended = testing::event(EventKind::Ended);
+        ended.channel.channel_id = String::from("UC2");
+        let digest = Digest::from_events("held", &[testing::event(EventKind::Live), testing::event(EventKind::Upload), ended]);
+        // The connection might not be up yet
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while notifier.notify_digest(&digest).is_err() && Instant::now() < deadline { std::thread::sleep(Duration::from_millis(50)); }
+
+        let messages: Vec<(String, bool, String)> = (0..3).map(|_| published.recv_timeout(Duration::from_secs(5)).unwrap()).collect();
+        assert_eq! (messages[0].0, "yt-notify/digest");
+        assert_eq! (messages[1], (String::from("yt-notify/UCuAXFkgsw1L7xaCfnd5JJOw/live_state"), true, String::from("ON")));
+        assert_eq! (messages[2], (String::from("yt-notify/UC2/live_state"), true, String::from("OFF")));
+    }
+
+    #[test]
+    fn events_that_are_not_sent_still_update_live_state() {
+        let (port, published) = broker();
+        let mut config = config(port);
+        config.qos = 0;
+        let notifier = MqttNotifier::new("mqtt", &config);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while notifier.update_state(&testing::event(EventKind::Ended)).is_err() && Instant::now() < deadline { std::thread::sleep(Duration::from_millis(50)); }
+        notifier.update_state(&testing::event(EventKind::Upload)).unwrap();
+
+        assert_eq! (published.recv_timeout(Duration::from_secs(5)).unwrap(), (String::from("yt-notify/UCuAXFkgsw1L7xaCfnd5JJOw/live_state"), true, String::from("OFF")));
+        // Only the live state; nothing about the event itself
+        assert! (published.recv_timeout(Duration::from_millis(300)).is_err());
+    }
+
+    #[test]
+    fn a_down_broker_never_blocks() {
+        // Nothing listens on the discard port
+        let notifier = MqttNotifier::new("mqtt", &config(9));
+        let started = Instant::now();
+        let results: Vec<_> = (0..40).map(|_| notifier.notify(&testing::event(EventKind::Live))).collect();
+
+        assert! (started.elapsed() < Duration::from_secs(2));
+        assert! (results.iter().any(|result| matches! (result, Err(NotifyError::Mqtt(_)))));
+    }
+}
@@ -0,0 +1,113 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use super::{Urgency, Notifier, NotifEvent, NotifyError, EventKind, Digest, http};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NtfyConfig {
+    // ntfy.sh or a self-hosted instance
+    #[serde(default = "default_server")]
+    pub server: String,
+    pub topic: String,
+    // An access token for protected topics
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default = "http::default_retries")]
+    pub retries: u32
+}
+
+pub struct NtfyNotifier {
+    name: String,
+    config: NtfyConfig
+}
+
+fn default_server() -> String { String::from("https://ntfy.sh") }
+// ntfy priorities go from 1 (min) to 5 (max)
+fn priority(urgency: Urgency) -> u8 {
+    match urgency {
+        Urgency::Low => 2,
+        Urgency::Normal => 3,
+        Urgency::Critical => 5
+    }
+}
+
+impl NtfyNotifier {
+    pub fn new(name: &str, config: &NtfyConfig) -> NtfyNotifier {
+        NtfyNotifier {
+            name: String::from(name),
+            config: config.clone()
+        }
+    }
+
+    // Uses ntfy's JSON publishing, which goes to the server root with the topic in the body
+    fn publish(&self, body: serde_json::Value) -> Result<(), NotifyError> {
+        let body = body.to_string();
+        let mut headers = HashMap::new();
+        if let Some(token) = &self.config.token {
+            headers.insert(String::from("Authorization"), format! ("Bearer {}", token));
+        }
+
+        let url = self.config.server.trim_end_matches('/');
+        http::send_with_retries(self.config.retries, || http::post_json(url, &headers, &body))?;
+        Ok(())
+    }
+}
+
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &str { &self.name }
+
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        self.publish(json! ({
+            "topic": self.config.topic,
+            "title": event.body,
+            "message": event.summary,
+            "priority": priority(event.prefs.get_urgency()),
+            "click": event.watch_url(),
+            "attach": event.thumbnail_url(),
+            "tags": [if event.kind == EventKind::Live { "red_circle" } else { "tv" }]
+        }))
+    }
+
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        self.publish(json! ({
+            "topic": self.config.topic,
+            "title": digest.title,
+            "message": digest.text(),
+            "priority": priority(Urgency::Normal),
+            "tags": ["tv"]
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notif::testing;
+
+    #[test]
+    fn events_are_published_to_the_topic() {
+        let (url, requests) = testing::serve(vec! [(200, vec! [])]);
+        let notifier = NtfyNotifier::new("ntfy", &NtfyConfig {
+            server: String::from(url.trim_end_matches("/hook")),
+            topic: String::from("new-videos"),
+            token: Some(String::from("tk_secret")),
+            retries: 0
+        });
+        let mut event = testing::event(EventKind::Live);
+        event.prefs.urgency(Urgency::Critical);
+        notifier.notify(&event).unwrap();
+
+        let request = requests.recv().unwrap();
+        // JSON publishing goes to the root, not /<topic>
+        assert_eq! (request.path, "/");
+        assert_eq! (request.headers["authorization"], "Bearer tk_secret");
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq! (body["topic"], "new-videos");
+        assert_eq! (body["title"], event.body);
+        assert_eq! (body["message"], event.summary);
+        assert_eq! (body["priority"], 5);
+        assert_eq! (body["click"], "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq! (body["tags"][0], "red_circle");
+    }
+}
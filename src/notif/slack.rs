@@ -0,0 +1,119 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use super::{Notifier, NotifEvent, NotifyError, EventKind, MessageFormat, Digest, http};
+use std::collections::HashMap;
+
+const MAX_SECTION_LENGTH: usize = 3000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SlackConfig {
+    // An incoming webhook URL
+    pub url: String,
+    #[serde(default)]
+    pub format: MessageFormat,
+    #[serde(default = "http::default_retries")]
+    pub retries: u32
+}
+
+pub struct SlackNotifier {
+    name: String,
+    config: SlackConfig
+}
+
+impl SlackNotifier {
+    pub fn new(name: &str, config: &SlackConfig) -> SlackNotifier {
+        SlackNotifier {
+            name: String::from(name),
+            config: config.clone()
+        }
+    }
+
+    // A Block Kit message; "text" is what shows up in the desktop/phone popup
+    fn build_payload(&self, event: &NotifEvent) -> serde_json::Value {
+        let live = event.kind == EventKind::Live;
+        let marker = if live { ":red_circle: *LIVE* " } else { "" };
+        let link = format! ("{}<{}|{}>", marker, event.watch_url(), escape_mrkdwn(&event.summary));
+        let fallback = format! ("{}: {}", event.body, event.summary);
+
+        match event.message_format(self.config.format) {
+            MessageFormat::Compact => {
+                json! ({
+                    "text": fallback,
+                    "blocks": [{
+                        "type": "section",
+                        "text": { "type": "mrkdwn", "text": format! ("{} - {}", link, escape_mrkdwn(&event.channel.name)) }
+                    }]
+                })
+            },
+            MessageFormat::Rich => {
+                let mut context = Vec::new();
+                if let Some(icon) = event.avatar_url() {
+                    context.push(json! ({ "type": "image", "image_url": icon, "alt_text": event.channel.name }));
+                }
+                context.push(json! ({
+                    "type": "mrkdwn",
+                    "text": format! ("*<{}|{}>*", event.channel_url(), escape_mrkdwn(&event.channel.name))
+                }));
+
+                json! ({
+                    "text": fallback,
+                    "blocks": [
+                        { "type": "context", "elements": context },
+                        {
+                            "type": "section",
+                            "text": { "type": "mrkdwn", "text": link },
+                            "accessory": {
+                                "type": "image",
+                                "image_url": event.thumbnail_url(),
+                                "alt_text": event.video.video_title
+                            }
+                        }
+                    ]
+                })
+            }
+        }
+    }
+
+    // A heading and a line of links; Slack won't take a section longer than 3000 characters
+    fn build_digest_payload(&self, digest: &Digest) -> serde_json::Value {
+        let mut text = format! ("*{}*", escape_mrkdwn(&digest.title));
+        for line in digest.lines.iter() {
+            let line = match &line.url {
+                Some(url) => format! ("\n<{}|{}>", url, escape_mrkdwn(&line.text)),
+                None => format! ("\n*{}*", escape_mrkdwn(&line.text))
+            };
+            if text.chars().count() + line.chars().count() > MAX_SECTION_LENGTH { break; }
+            text.push_str(&line);
+        }
+
+        json! ({
+            "text": digest.title,
+            "blocks": [{ "type": "section", "text": { "type": "mrkdwn", "text": text } }]
+        })
+    }
+
+    fn post(&self, payload: serde_json::Value) -> Result<(), NotifyError> {
+        let body = payload.to_string();
+        let headers = HashMap::new();
+        http::send_with_retries(self.config.retries, || http::post_json(&self.config.url, &headers, &body))?;
+        Ok(())
+    }
+}
+
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &str { &self.name }
+
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        self.post(self.build_payload(event))
+    }
+
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        self.post(self.build_digest_payload(digest))
+    }
+}
+
+// Slack only needs these three escaped in mrkdwn
+fn escape_mrkdwn(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,190 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use super::{Notifier, NotifEvent, NotifyError, EventKind, Digest, http};
+use std::collections::HashMap;
+
+// Where the bot token is read from if it isn't in the config
+pub const TOKEN_ENV_VAR: &str = "YTN_TELEGRAM_TOKEN";
+// Telegram won't take a message any longer than this
+const MAX_MESSAGE_LENGTH: usize = 4096;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TelegramConfig {
+    // Falls back to $YTN_TELEGRAM_TOKEN, so it doesn't have to sit in the config
+    #[serde(default)]
+    pub token: Option<String>,
+    // A user, group or @channelusername
+    pub chat_id: String,
+    // Only needs changing for a self-hosted Bot API server (or a test stand-in)
+    #[serde(default = "default_api_base")]
+    pub api_base: String,
+    // Send the thumbnail with the message
+    #[serde(default = "default_photo")]
+    pub photo: bool,
+    #[serde(default = "http::default_retries")]
+    pub retries: u32
+}
+
+pub struct TelegramNotifier {
+    name: String,
+    config: TelegramConfig
+}
+
+fn default_api_base() -> String { String::from("https://api.telegram.org") }
+fn default_photo() -> bool { true }
+impl TelegramNotifier {
+    pub fn new(name: &str, config: &TelegramConfig) -> TelegramNotifier {
+        TelegramNotifier {
+            name: String::from(name),
+            config: config.clone()
+        }
+    }
+
+    fn token(&self) -> Result<String, NotifyError> {
+        if let Some(token) = &self.config.token { return Ok(token.clone()); }
+        std::env::var(TOKEN_ENV_VAR).map_err(|_| NotifyError::Config(format! ("no bot token in the config or ${}", TOKEN_ENV_VAR)))
+    }
+
+    fn call(&self, method: &str, body: &serde_json::Value) -> Result<(), NotifyError> {
+        let url = format! ("{}/bot{}/{}", self.config.api_base.trim_end_matches('/'), self.token()?, method);
+        let body = body.to_string();
+        let headers = HashMap::new();
+        http::send_with_retries(self.config.retries, || http::post_json(&url, &headers, &body))?;
+        Ok(())
+    }
+}
+
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &str { &self.name }
+
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        let marker = if event.kind == EventKind::Live { "\u{1F534} " } else { "" };
+        let text = format! ("{}*{}*\n{}", marker, escape_markdown(&event.summary), escape_markdown(&event.body));
+        let keyboard = json! ({ "inline_keyboard": [[{ "text": "Watch", "url": event.watch_url() }]] });
+
+        if self.config.photo {
+            let photo = json! ({
+                "chat_id": self.config.chat_id,
+                "photo": event.thumbnail_url(),
+                "caption": text,
+                "parse_mode": "MarkdownV2",
+                "reply_markup": keyboard
+            });
+            // Telegram can't always fetch the thumbnail; the text alone is better than nothing
+            if self.call("sendPhoto", &photo).is_ok() { return Ok(()); }
+        }
+
+        let message = json! ({
+            "chat_id": self.config.chat_id,
+            "text": text,
+            "parse_mode": "MarkdownV2",
+            "reply_markup": keyboard
+        });
+        self.call("sendMessage", &message)
+    }
+
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        // A long report is cut short rather than turned away; the limit's on the escaped text
+        let mut text = format! ("*{}*", escape_markdown(&digest.title));
+        for line in digest.lines.iter() {
+            let line = match &line.url {
+                // Inside a link's URL only ) and \ need escaping
+                Some(url) => format! ("\n[{}]({})", escape_markdown(&line.text), url.replace('\\', "\\\\").replace(')', "\\)")),
+                None => format! ("\n*{}*", escape_markdown(&line.text))
+            };
+            if text.chars().count() + line.chars().count() > MAX_MESSAGE_LENGTH { break; }
+            text.push_str(&line);
+        }
+
+        let message = json! ({
+            "chat_id": self.config.chat_id,
+            "text": text,
+            "parse_mode": "MarkdownV2",
+            "disable_web_page_preview": true
+        });
+        self.call("sendMessage", &message)
+    }
+}
+
+// Everything MarkdownV2 treats as special has to be backslashed
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\_*[]()~`>#+-=|{}.!".contains(c) { escaped.push('\\'); }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notif::{testing, DigestLine};
+
+    fn notifier(url: &str, photo: bool) -> TelegramNotifier {
+        TelegramNotifier::new("telegram", &TelegramConfig {
+            token: Some(String::from("123:abc")),
+            chat_id: String::from("@ytn"),
+            api_base: String::from(url.trim_end_matches("/hook")),
+            photo,
+            retries: 0
+        })
+    }
+
+    #[test]
+    fn events_are_sent_as_photos() {
+        let (url, requests) = testing::serve(vec! [(200, vec! [])]);
+        notifier(&url, true).notify(&testing::event(EventKind::Live)).unwrap();
+
+        let request = requests.recv().unwrap();
+        assert_eq! (request.path, "/bot123:abc/sendPhoto");
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq! (body["chat_id"], "@ytn");
+        assert_eq! (body["photo"], "https://i.ytimg.com/vi/dQw4w9WgXcQ/mqdefault.jpg");
+        assert_eq! (body["parse_mode"], "MarkdownV2");
+        assert! (body["caption"].as_str().unwrap().starts_with("\u{1F534} *"));
+        assert_eq! (body["reply_markup"]["inline_keyboard"][0][0]["url"], "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+    }
+
+    #[test]
+    fn a_photo_telegram_cannot_fetch_falls_back_to_text() {
+        let (url, requests) = testing::serve(vec! [(400, vec! []), (200, vec! [])]);
+        notifier(&url, true).notify(&testing::event(EventKind::Upload)).unwrap();
+
+        assert_eq! (requests.recv().unwrap().path, "/bot123:abc/sendPhoto");
+        let request = requests.recv().unwrap();
+        assert_eq! (request.path, "/bot123:abc/sendMessage");
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert! (body["text"].as_str().unwrap().contains("Never Gonna Give You Up"));
+        assert! (body.get("photo").is_none());
+    }
+
+    #[test]
+    fn the_token_can_come_from_the_environment() {
+        let (url, requests) = testing::serve(vec! [(200, vec! [])]);
+        let mut notifier = notifier(&url, false);
+        notifier.config.token = None;
+        std::env::set_var(TOKEN_ENV_VAR, "456:env");
+        notifier.notify(&testing::event(EventKind::Upload)).unwrap();
+        std::env::remove_var(TOKEN_ENV_VAR);
+
+        assert_eq! (requests.recv().unwrap().path, "/bot456:env/sendMessage");
+        assert! (matches! (notifier.notify(&testing::event(EventKind::Upload)), Err(NotifyError::Config(_))));
+    }
+
+    #[test]
+    fn long_digests_are_cut_to_fit() {
+        let (url, requests) = testing::serve(vec! [(200, vec! [])]);
+        // Every . gets a backslash, so these are twice as long once escaped
+        let lines = (0..100).map(|i| DigestLine { text: format! ("{}{}", i, ".".repeat(30)), url: Some(String::from("https://youtu.be/x")) }).collect();
+        notifier(&url, false).notify_digest(&Digest { title: String::from("Weekly report"), lines, events: Vec::new() }).unwrap();
+
+        let body: serde_json::Value = serde_json::from_str(&requests.recv().unwrap().body).unwrap();
+        let text = body["text"].as_str().unwrap();
+        assert! (text.chars().count() <= MAX_MESSAGE_LENGTH);
+        assert! (text.starts_with("*Weekly report*\n[0\\.\\."));
+        // Whole lines only
+        assert! (text.ends_with("(https://youtu.be/x)"));
+    }
+}
@@ -0,0 +1,98 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use super::{Notifier, NotifEvent, NotifyError, EventKind, Digest};
+use std::io::Write;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TerminalStyle {
+    Plain,
+    // ANSI colours
+    Color,
+    // OSC 9 (iTerm2, Windows Terminal, kitty...) pops up a notification from the terminal itself
+    Osc9,
+    // OSC 777 (urxvt, foot, VTE-based terminals) does the same with a separate title
+    Osc777
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TerminalConfig {
+    #[serde(default = "default_style")]
+    pub style: TerminalStyle
+}
+
+// Prints notifications to stdout
+pub struct TerminalNotifier {
+    name: String,
+    config: TerminalConfig
+}
+
+fn default_style() -> TerminalStyle { TerminalStyle::Color }
+
+impl TerminalNotifier {
+    pub fn new(name: &str, config: &TerminalConfig) -> TerminalNotifier {
+        TerminalNotifier {
+            name: String::from(name),
+            config: config.clone()
+        }
+    }
+
+    // What the desktop notifier falls back on when there's no notification server
+    pub fn fallback() -> TerminalNotifier {
+        TerminalNotifier::new("terminal", &TerminalConfig { style: default_style() })
+    }
+
+    fn format(&self, event: &NotifEvent) -> String {
+        let time = chrono::Local::now().format("%Y-%m-%d %H:%M");
+        let summary = strip_control(&event.summary);
+        let body = strip_control(&event.body);
+
+        match self.config.style {
+            TerminalStyle::Plain => format! ("[{}] {}: {} {}", time, body, summary, event.watch_url()),
+            TerminalStyle::Color => {
+                let marker = if event.kind == EventKind::Live { "\x1b[91m\u{25CF} LIVE\x1b[0m " } else { "" };
+                format! ("\x1b[90m[{}]\x1b[0m {}\x1b[93m{}\x1b[0m: \x1b[1m{}\x1b[0m \x1b[94m{}\x1b[0m",
+                    time, marker, body, summary, event.watch_url())
+            },
+            TerminalStyle::Osc9 => format! ("\x1b]9;{}: {}\x07", body, summary),
+            TerminalStyle::Osc777 => format! ("\x1b]777;notify;{};{}\x07", body.replace(';', ","), summary.replace(';', ","))
+        }
+    }
+
+    // The title, then one line per event; the OSC styles can only pop up the title
+    fn format_digest(&self, digest: &Digest) -> String {
+        let time = chrono::Local::now().format("%Y-%m-%d %H:%M");
+        let title = strip_control(&digest.title);
+        let lines: Vec<String> = digest.text().lines().map(|line| format! ("  {}", strip_control(line))).collect();
+
+        match self.config.style {
+            TerminalStyle::Plain => format! ("[{}] {}\n{}", time, title, lines.join("\n")),
+            TerminalStyle::Color => format! ("\x1b[90m[{}]\x1b[0m \x1b[1m{}\x1b[0m\n{}", time, title, lines.join("\n")),
+            TerminalStyle::Osc9 => format! ("\x1b]9;{}\x07", title),
+            TerminalStyle::Osc777 => format! ("\x1b]777;notify;yt-notify;{}\x07", title.replace(';', ","))
+        }
+    }
+
+    fn print(&self, text: &str) -> Result<(), NotifyError> {
+        let mut stdout = std::io::stdout();
+        writeln! (stdout, "{}", text).map_err(|e| NotifyError::Terminal(e.to_string()))?;
+        stdout.flush().map_err(|e| NotifyError::Terminal(e.to_string()))
+    }
+}
+
+impl Notifier for TerminalNotifier {
+    fn name(&self) -> &str { &self.name }
+
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        self.print(&self.format(event))
+    }
+
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        self.print(&self.format_digest(digest))
+    }
+}
+
+// A title with escape codes in it could mess with the terminal
+fn strip_control(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control()).collect()
+}
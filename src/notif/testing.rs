@@ -0,0 +1,86 @@
+// Bits the notifier tests share: a sample event and a tiny HTTP server to send it to
+use super::{NotifEvent, NotifPrefs, EventKind};
+use crate::config::Config;
+use crate::youtube::{Video, Channel};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::time::Instant;
+
+pub fn video() -> Video {
+    Video {
+        video_title: String::from("Never Gonna Give You Up"),
+        video_id: String::from("dQw4w9WgXcQ"),
+        video_desc: String::from("The official video"),
+        is_live: false,
+        tags: None,
+        is_upcoming: false,
+        duration: Some(213),
+        published: Some(1256453999)
+    }
+}
+
+pub fn channel() -> Channel {
+    serde_json::from_value(serde_json::json! ({
+        "name": "Rick Astley", "channel_id": "UCuAXFkgsw1L7xaCfnd5JJOw", "channel_type": "Channel",
+        "filter": [], "path": "/nonexistent/UCuAXFkgsw1L7xaCfnd5JJOw.json", "pic_path": "/nonexistent/UCuAXFkgsw1L7xaCfnd5JJOw.png",
+        "archive": false, "archive_filter": null, "latest_ids": [null, null]
+    })).unwrap()
+}
+
+pub fn event(kind: EventKind) -> NotifEvent {
+    let mut vid = video();
+    vid.is_live = kind == EventKind::Live;
+    NotifEvent::with_kind(kind, &vid, &channel(), &NotifPrefs::new(), &Config::default())
+}
+
+// What the server was sent
+pub struct Request {
+    pub path: String,
+    // Names in lower case
+    pub headers: HashMap<String, String>,
+    pub body: String,
+    pub received: Instant
+}
+
+// Answers each connection with the next of the given responses (a status and extra headers),
+// then stops; gives back its URL and where the requests it got will turn up
+pub fn serve(responses: Vec<(u16, Vec<(&'static str, &'static str)>)>) -> (String, mpsc::Receiver<Request>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format! ("http://{}/hook", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        for (status, headers) in responses.into_iter() {
+            let (stream, _) = if let Ok(accepted) = listener.accept() { accepted } else { return; };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let path = String::from(request_line.split_whitespace().nth(1).unwrap_or(""));
+
+            let mut request_headers = HashMap::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 || line.trim().is_empty() { break; }
+                if let Some((name, value)) = line.split_once(':') {
+                    request_headers.insert(name.trim().to_lowercase(), String::from(value.trim()));
+                }
+            }
+            let length = request_headers.get("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+            let mut body = vec! [0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            // Pass it on before answering, so it's there by the time the client's done
+            let _ = tx.send(Request { path, headers: request_headers, body: String::from_utf8(body).unwrap(), received: Instant::now() });
+
+            let mut response = format! ("HTTP/1.1 {} Whatever\r\nContent-Length: 0\r\nConnection: close\r\n", status);
+            for (name, value) in headers.iter() { response.push_str(&format! ("{}: {}\r\n", name, value)); }
+            response.push_str("\r\n");
+            let mut stream = stream;
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (url, rx)
+}
@@ -0,0 +1,192 @@
+// POSTs every event to a URL as JSON:
+//
+// ```json
+// {
+//   "event": "upload",
+//   "video_id": "dQw4w9WgXcQ",
+//   "title": "Never Gonna Give You Up",
+//   "channel_name": "Rick Astley",
+//   "channel_id": "UCuAXFkgsw1L7xaCfnd5JJOw",
+//   "is_live": false,
+//   "url": "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+//   "timestamp": "2022-03-01T12:00:00+00:00"
+// }
+// ```
+//
+// `event` is `upload`, `live`, `premiere`, `reminder` or `ended`. Digests (like the summary after
+// quiet hours) are sent as `{"event": "digest", "title": ..., "lines": [{"text": ..., "url": ...}],
+// "events": [...], "timestamp": ...}` with an object like the one above for each event; reports
+// only have lines. If a secret is set, the body is signed with HMAC-SHA256 and sent as
+// `X-YTN-Signature: sha256=<hex digest>`.
+extern crate hmac;
+extern crate sha2;
+extern crate hex;
+extern crate serde;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use serde::{Serialize, Deserialize};
+use super::{Notifier, NotifEvent, NotifyError, EventKind, Digest, DigestLine, http};
+use std::collections::HashMap;
+
+pub const SIGNATURE_HEADER: &str = "X-YTN-Signature";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+    // Sent with every request, e.g. for an Authorization token
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    // Used to sign the body so the receiver knows it's from us
+    #[serde(default)]
+    pub secret: Option<String>,
+    // How many times to try again after the first attempt fails
+    #[serde(default = "http::default_retries")]
+    pub retries: u32
+}
+
+#[derive(Serialize, Debug)]
+pub struct WebhookPayload<'a> {
+    pub event: EventKind,
+    pub video_id: &'a str,
+    pub title: &'a str,
+    pub channel_name: &'a str,
+    pub channel_id: &'a str,
+    pub is_live: bool,
+    pub url: String,
+    pub timestamp: String
+}
+
+#[derive(Serialize, Debug)]
+pub struct DigestPayload<'a> {
+    pub event: &'static str,
+    pub title: &'a str,
+    pub lines: &'a [DigestLine],
+    pub events: Vec<WebhookPayload<'a>>,
+    pub timestamp: String
+}
+
+pub struct WebhookNotifier {
+    name: String,
+    config: WebhookConfig
+}
+
+impl<'a> WebhookPayload<'a> {
+    pub fn from_event(event: &'a NotifEvent) -> WebhookPayload<'a> {
+        WebhookPayload {
+            event: event.kind,
+            video_id: &event.video.video_id,
+            title: &event.video.video_title,
+            channel_name: &event.channel.name,
+            channel_id: &event.channel.channel_id,
+            is_live: event.video.is_live,
+            url: event.watch_url(),
+            timestamp: chrono::Utc::now().to_rfc3339()
+        }
+    }
+}
+
+impl<'a> DigestPayload<'a> {
+    pub fn from_digest(digest: &'a Digest) -> DigestPayload<'a> {
+        DigestPayload {
+            event: "digest",
+            title: &digest.title,
+            lines: &digest.lines,
+            events: digest.events.iter().map(WebhookPayload::from_event).collect(),
+            timestamp: chrono::Utc::now().to_rfc3339()
+        }
+    }
+}
+
+impl WebhookNotifier {
+    pub fn new(name: &str, config: &WebhookConfig) -> WebhookNotifier {
+        WebhookNotifier {
+            name: String::from(name),
+            config: config.clone()
+        }
+    }
+
+    fn post(&self, body: &str) -> Result<(), NotifyError> {
+        let mut headers = self.config.headers.clone();
+        if let Some(secret) = &self.config.secret {
+            headers.insert(String::from(SIGNATURE_HEADER), format! ("sha256={}", sign(secret, body)));
+        }
+
+        http::send_with_retries(self.config.retries, || http::post_json(&self.config.url, &headers, body))?;
+        Ok(())
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str { &self.name }
+
+    fn notify(&self, event: &NotifEvent) -> Result<(), NotifyError> {
+        self.post(&serde_json::to_string(&WebhookPayload::from_event(event)).unwrap())
+    }
+
+    fn notify_digest(&self, digest: &Digest) -> Result<(), NotifyError> {
+        self.post(&serde_json::to_string(&DigestPayload::from_digest(digest)).unwrap())
+    }
+}
+
+// Hex HMAC-SHA256 of the body
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notif::testing;
+
+    fn notifier(url: String) -> WebhookNotifier {
+        let mut headers = HashMap::new();
+        headers.insert(String::from("Authorization"), String::from("Bearer token"));
+        WebhookNotifier::new("hook", &WebhookConfig { url, headers, secret: Some(String::from("s3cret")), retries: 0 })
+    }
+
+    #[test]
+    fn events_are_posted_signed_with_their_fields() {
+        let (url, requests) = testing::serve(vec! [(200, vec! [])]);
+        notifier(url).notify(&testing::event(EventKind::Live)).unwrap();
+
+        let request = requests.recv().unwrap();
+        assert_eq! (request.path, "/hook");
+        assert_eq! (request.headers["authorization"], "Bearer token");
+        assert! (request.headers["content-type"].starts_with("application/json"));
+        assert_eq! (request.headers["x-ytn-signature"], format! ("sha256={}", sign("s3cret", &request.body)));
+
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq! (body["event"], "live");
+        assert_eq! (body["video_id"], "dQw4w9WgXcQ");
+        assert_eq! (body["title"], "Never Gonna Give You Up");
+        assert_eq! (body["channel_name"], "Rick Astley");
+        assert_eq! (body["channel_id"], "UCuAXFkgsw1L7xaCfnd5JJOw");
+        assert_eq! (body["is_live"], true);
+        assert_eq! (body["url"], "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert! (chrono::DateTime::parse_from_rfc3339(body["timestamp"].as_str().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn digests_carry_their_lines_and_events() {
+        let (url, requests) = testing::serve(vec! [(200, vec! [])]);
+        let events = [testing::event(EventKind::Upload), testing::event(EventKind::Ended)];
+        notifier(url).notify_digest(&Digest::from_events("2 new videos", &events)).unwrap();
+
+        let request = requests.recv().unwrap();
+        assert_eq! (request.headers["x-ytn-signature"], format! ("sha256={}", sign("s3cret", &request.body)));
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq! (body["event"], "digest");
+        assert_eq! (body["title"], "2 new videos");
+        assert_eq! (body["lines"].as_array().unwrap().len(), 2);
+        assert_eq! (body["lines"][0]["url"], "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq! (body["events"][1]["event"], "ended");
+    }
+
+    #[test]
+    fn signatures_are_hmac_sha256() {
+        // From RFC 4231, test case 2
+        assert_eq! (sign("Jefe", "what do ya want for nothing?"), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+}
@@ -0,0 +1,158 @@
+extern crate chrono;
+extern crate chrono_tz;
+extern crate serde;
+use chrono::{DateTime, Datelike, NaiveTime, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+use crate::config;
+use crate::notif::NotifEvent;
+use crate::youtube::{Video, Channel};
+use std::path::{PathBuf, Path};
+
+// Where held events wait for quiet hours to end, so a restart doesn't lose them
+const HELD_FILE_NAME: &str = "held.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Day {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun
+}
+
+// Quiet from start until end ("HH:MM"); if end is earlier than start, it runs over midnight
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuietWindow {
+    // The days it starts on; every day if empty
+    #[serde(default)]
+    pub days: Vec<Day>,
+    pub start: String,
+    pub end: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct QuietHours {
+    // An IANA name like "Europe/London"; the system's timezone if None
+    pub timezone: Option<String>,
+    pub windows: Vec<QuietWindow>,
+    // Videos with any of these in the title or description are sent anyway
+    pub break_through_keywords: Vec<String>
+}
+
+// Events held back during quiet hours
+pub struct HeldEvents {
+    path: PathBuf,
+    events: Vec<NotifEvent>
+}
+
+impl Day {
+    pub fn weekday(&self) -> Weekday {
+        match self {
+            Day::Mon => Weekday::Mon,
+            Day::Tue => Weekday::Tue,
+            Day::Wed => Weekday::Wed,
+            Day::Thu => Weekday::Thu,
+            Day::Fri => Weekday::Fri,
+            Day::Sat => Weekday::Sat,
+            Day::Sun => Weekday::Sun
+        }
+    }
+}
+
+impl QuietWindow {
+    fn validate(&self) -> Result<(), String> {
+        parse_time(&self.start)?;
+        parse_time(&self.end)?;
+        Ok(())
+    }
+
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.iter().any(|d| d.weekday() == day)
+    }
+
+    fn covers(&self, day: Weekday, time: NaiveTime) -> bool {
+        let (start, end) = if let (Ok(start), Ok(end)) = (parse_time(&self.start), parse_time(&self.end)) { (start, end) } else { return false; };
+        if start <= end {
+            self.starts_on(day) && start <= time && time < end
+        } else {
+            // The part after midnight belongs to the window that started the day before
+            (self.starts_on(day) && start <= time) || (self.starts_on(day.pred()) && time < end)
+        }
+    }
+}
+
+impl QuietHours {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(timezone) = &self.timezone {
+            timezone.parse::<Tz>().map_err(|_| format! ("unknown timezone \"{}\"", timezone))?;
+        }
+        for window in self.windows.iter() {
+            window.validate()?;
+        }
+        Ok(())
+    }
+
+    pub fn is_quiet(&self, now: DateTime<Utc>) -> bool {
+        if self.windows.is_empty() { return false; }
+
+        let (day, time) = match self.timezone.as_ref().and_then(|timezone| timezone.parse::<Tz>().ok()) {
+            Some(timezone) => { let local = now.with_timezone(&timezone); (local.weekday(), local.time()) },
+            None => { let local = now.with_timezone(&chrono::Local); (local.weekday(), local.time()) }
+        };
+        // Seconds would only get in the way of a window ending on the minute
+        let time = time.with_second(0).unwrap_or(time);
+
+        self.windows.iter().any(|window| window.covers(day, time))
+    }
+
+    // Whether the video gets sent even during quiet hours
+    pub fn breaks_through(&self, vid: &Video, channel: &Channel) -> bool {
+        if channel.break_quiet_hours { return true; }
+        let title = vid.video_title.to_lowercase();
+        let desc = vid.video_desc.to_lowercase();
+        self.break_through_keywords.iter().any(|keyword| {
+            let keyword = keyword.to_lowercase();
+            title.contains(&keyword) || desc.contains(&keyword)
+        })
+    }
+}
+
+impl HeldEvents {
+    // Pick up whatever was held before the last shutdown
+    pub fn load(cfg_path: &Path) -> HeldEvents {
+        let mut path = config::state_dir(cfg_path);
+        path.push(HELD_FILE_NAME);
+
+        let events = config::load_state(&path, "held notifications");
+        HeldEvents { path, events }
+    }
+
+    pub fn hold(&mut self, event: NotifEvent) {
+        self.events.push(event);
+        if self.save().is_err() { eprintln! ("Could not save the held notifications to {}", self.path.display()); }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    // Hand back everything that was held and forget about it
+    pub fn take(&mut self) -> Vec<NotifEvent> {
+        let events = std::mem::take(&mut self.events);
+        if self.save().is_err() { eprintln! ("Could not save the held notifications to {}", self.path.display()); }
+        events
+    }
+
+    fn save(&self) -> Result<(), ()> {
+        config::save_state(&self.path, &self.events)
+    }
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format! ("\"{}\" isn't a time like 22:30", time))
+}
@@ -0,0 +1,334 @@
+extern crate chrono;
+extern crate serde;
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Utc};
+use serde::{Serialize, Deserialize};
+use crate::config;
+use crate::history::{self, HistoryAction, HistoryEntry};
+use crate::notif::{self, Notifier, Digest, DigestLine};
+use crate::notif::email::escape_html;
+use crate::quiet::Day;
+use crate::templates::format_duration;
+use crate::youtube;
+use std::path::{PathBuf, Path};
+
+// When the last report went out, so a restart doesn't send it twice
+const REPORT_FILE_NAME: &str = "report.json";
+const REPORTS_DIR_NAME: &str = "reports";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportPeriod {
+    Daily,
+    Weekly
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Markdown,
+    Html
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ReportConfig {
+    // How often to send a report; no reports if None
+    pub period: Option<ReportPeriod>,
+    // The hour of the day (local time) it goes out at
+    pub hour: u32,
+    // The day weekly reports go out on
+    pub weekday: Day,
+    // The notifiers it's sent through; every one if None, and none if empty
+    pub notifiers: Option<Vec<String>>,
+    // Also written into dir in each of these formats
+    pub formats: Vec<ReportFormat>,
+    // Where written reports go; "reports" next to the channel files if None
+    pub dir: Option<PathBuf>
+}
+
+impl Default for ReportConfig {
+    fn default() -> ReportConfig {
+        ReportConfig {
+            period: None,
+            hour: 8,
+            weekday: Day::Mon,
+            notifiers: None,
+            formats: Vec::new(),
+            dir: None
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ReportState {
+    last_sent: i64
+}
+
+// Sends a report whenever the next one's due
+pub struct Reporter {
+    cfg_path: PathBuf,
+    path: PathBuf,
+    last_sent: Option<i64>
+}
+
+// Everything found in one period
+pub struct Report {
+    pub period: ReportPeriod,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub channels: Vec<ChannelReport>,
+    // The Invidious or Piped instance its links point to, if any
+    pub frontend: Option<String>
+}
+
+pub struct ChannelReport {
+    pub name: String,
+    pub videos: Vec<ReportVideo>
+}
+
+pub struct ReportVideo {
+    pub video_id: String,
+    pub title: String,
+    pub duration: Option<u64>,
+    pub archived: bool
+}
+
+impl ReportPeriod {
+    fn days(&self) -> i64 {
+        match self {
+            ReportPeriod::Daily => 1,
+            ReportPeriod::Weekly => 7
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ReportPeriod::Daily => "daily",
+            ReportPeriod::Weekly => "weekly"
+        }
+    }
+}
+
+impl ReportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html"
+        }
+    }
+}
+
+impl ReportConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.hour > 23 { return Err(format! ("{} isn't an hour of the day", self.hour)); }
+        Ok(())
+    }
+
+    // The latest time a report should have gone out by now
+    fn last_scheduled(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let period = self.period?;
+        let mut date = now.with_timezone(&Local).date_naive();
+        if period == ReportPeriod::Weekly {
+            while date.weekday() != self.weekday.weekday() { date = date.pred_opt()?; }
+        }
+
+        let at = |date: NaiveDate| date.and_hms_opt(self.hour, 0, 0)
+            .and_then(|time| Local.from_local_datetime(&time).earliest())
+            .map(|time| time.with_timezone(&Utc));
+        let scheduled = at(date)?;
+        if scheduled <= now { Some(scheduled) } else { at(date - chrono::Duration::days(period.days())) }
+    }
+
+    fn dir(&self, cfg_path: &Path) -> PathBuf {
+        self.dir.clone().unwrap_or_else(|| {
+            let mut dir = cfg_path.to_path_buf();
+            dir.push(REPORTS_DIR_NAME);
+            dir
+        })
+    }
+}
+
+impl Reporter {
+    pub fn load(cfg_path: &Path) -> Reporter {
+        let mut path = config::state_dir(cfg_path);
+        path.push(REPORT_FILE_NAME);
+
+        let last_sent = config::load_state::<Option<ReportState>>(&path, "report schedule").map(|state| state.last_sent);
+        Reporter { cfg_path: cfg_path.to_path_buf(), path, last_sent }
+    }
+
+    // Send and write out a report if one's due since the last
+    pub fn run_if_due(&mut self, config: &ReportConfig, frontend: Option<&str>, notifiers: &[Box<dyn Notifier>], now: DateTime<Utc>) {
+        let (period, scheduled) = if let (Some(period), Some(scheduled)) = (config.period, config.last_scheduled(now)) { (period, scheduled) } else { return; };
+
+        match self.last_sent {
+            // The first time around, start from now rather than sending a report straight away
+            None => { self.mark_sent(now.timestamp()); return; },
+            Some(last_sent) if last_sent >= scheduled.timestamp() => return,
+            Some(_) => {}
+        }
+
+        let report = Report::build(&history::load(&self.cfg_path), period, scheduled, frontend);
+        if report.channels.is_empty() {
+            println! ("Nothing found for the {} report; not sending it", period.name());
+        } else {
+            self.write(config, &report);
+            let digest = report.digest();
+            notif::dispatch_report(notifiers, &digest, config.notifiers.as_deref());
+        }
+        self.mark_sent(now.timestamp());
+    }
+
+    fn write(&self, config: &ReportConfig, report: &Report) {
+        if config.formats.is_empty() { return; }
+        let dir = config.dir(&self.cfg_path);
+        if std::fs::create_dir_all(&dir).is_err() {
+            eprintln! ("Could not make the reports directory {}", dir.display());
+            return;
+        }
+
+        for format in config.formats.iter() {
+            let mut path = dir.clone();
+            path.push(format! ("{}-{}.{}", report.period.name(), report.end.with_timezone(&Local).format("%Y-%m-%d"), format.extension()));
+            let contents = match format {
+                ReportFormat::Markdown => report.markdown(),
+                ReportFormat::Html => report.html()
+            };
+            if std::fs::write(&path, contents).is_err() {
+                eprintln! ("Could not write the report to {}", path.display());
+            } else {
+                println! ("Wrote the {} report to {}", report.period.name(), path.display());
+            }
+        }
+    }
+
+    fn mark_sent(&mut self, time: i64) {
+        self.last_sent = Some(time);
+        if self.save().is_err() { eprintln! ("Could not save when the last report went out to {}", self.path.display()); }
+    }
+
+    fn save(&self) -> Result<(), ()> {
+        let last_sent = if let Some(last_sent) = self.last_sent { last_sent } else { return Ok(()); };
+        config::save_state(&self.path, &ReportState { last_sent })
+    }
+}
+
+impl Report {
+    // Everything found in the period leading up to end, grouped by channel
+    pub fn build(entries: &[HistoryEntry], period: ReportPeriod, end: DateTime<Utc>, frontend: Option<&str>) -> Report {
+        let start = end - chrono::Duration::days(period.days());
+        let mut channels: Vec<(String, ChannelReport)> = Vec::new();
+
+        for entry in entries.iter() {
+            if entry.action != HistoryAction::Found || entry.time < start.timestamp() || entry.time >= end.timestamp() { continue; }
+
+            let channel = match channels.iter().position(|(id, _)| *id == entry.channel_id) {
+                Some(i) => &mut channels[i].1,
+                None => {
+                    channels.push((entry.channel_id.clone(), ChannelReport { name: entry.channel_name.clone(), videos: Vec::new() }));
+                    &mut channels.last_mut().unwrap().1
+                }
+            };
+            // A stream turns up more than once; it's only as long as it ever got
+            match channel.videos.iter_mut().find(|video| video.video_id == entry.video_id) {
+                Some(video) => { video.duration = video.duration.max(entry.duration); },
+                None => channel.videos.push(ReportVideo {
+                    video_id: entry.video_id.clone(),
+                    title: entry.title.clone(),
+                    duration: entry.duration,
+                    archived: false
+                })
+            }
+        }
+
+        let mut channels: Vec<ChannelReport> = channels.into_iter().map(|(_, channel)| channel).collect();
+        for video in channels.iter_mut().flat_map(|channel| channel.videos.iter_mut()) {
+            video.archived = entries.iter().any(|entry| entry.action == HistoryAction::Archived && entry.video_id == video.video_id);
+        }
+        Report { period, start, end, channels, frontend: frontend.map(String::from) }
+    }
+
+    fn url(&self, video: &ReportVideo) -> String {
+        youtube::link_url(&video.video_id, self.frontend.as_deref())
+    }
+
+    pub fn title(&self) -> String {
+        let period = match self.period {
+            ReportPeriod::Daily => "Daily",
+            ReportPeriod::Weekly => "Weekly"
+        };
+        format! ("{} digest, {} to {}", period,
+            self.start.with_timezone(&Local).format("%Y-%m-%d"),
+            self.end.with_timezone(&Local).format("%Y-%m-%d"))
+    }
+
+    // "12 videos from 3 channels, 2 archived, 4:35:12 in total"
+    pub fn totals(&self) -> String {
+        let all: Vec<&ReportVideo> = self.channels.iter().flat_map(|channel| channel.videos.iter()).collect();
+        let archived = all.iter().filter(|video| video.archived).count();
+        let length: u64 = all.iter().filter_map(|video| video.duration).sum();
+        let channels = if self.channels.len() == 1 { String::from("1 channel") } else { format! ("{} channels", self.channels.len()) };
+        format! ("{} from {}, {} archived, {} in total", videos(all.len()), channels, archived, format_duration(length))
+    }
+
+    // To send through the notifiers: a heading for each channel with its videos under it
+    pub fn digest(&self) -> Digest {
+        let mut lines = Vec::new();
+        for channel in self.channels.iter() {
+            lines.push(DigestLine { text: format! ("{} ({})", channel.name, videos(channel.videos.len())), url: None });
+            for video in channel.videos.iter() {
+                lines.push(DigestLine { text: format! ("{} ({})", video.title, video.details()), url: Some(self.url(video)) });
+            }
+        }
+        lines.push(DigestLine { text: self.totals(), url: None });
+
+        Digest { title: self.title(), lines, events: Vec::new() }
+    }
+
+    pub fn markdown(&self) -> String {
+        let mut text = format! ("# {}\n\n{}\n", self.title(), self.totals());
+        for channel in self.channels.iter() {
+            text.push_str(&format! ("\n## {} ({})\n\n", escape_markdown(&channel.name), videos(channel.videos.len())));
+            for video in channel.videos.iter() {
+                text.push_str(&format! ("- [{}]({}) ({})\n", escape_markdown(&video.title), self.url(video), video.details()));
+            }
+        }
+        text
+    }
+
+    pub fn html(&self) -> String {
+        let mut text = format! ("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0}</title></head><body>\n<h1>{0}</h1>\n<p>{1}</p>\n",
+            escape_html(&self.title()), escape_html(&self.totals()));
+        for channel in self.channels.iter() {
+            text.push_str(&format! ("<h2>{} ({})</h2>\n<ul>\n", escape_html(&channel.name), videos(channel.videos.len())));
+            for video in channel.videos.iter() {
+                text.push_str(&format! ("<li><a href=\"{}\">{}</a> ({})</li>\n",
+                    escape_html(&self.url(video)), escape_html(&video.title), escape_html(&video.details())));
+            }
+            text.push_str("</ul>\n");
+        }
+        text.push_str("</body></html>\n");
+        text
+    }
+}
+
+impl ReportVideo {
+    // "12:34, archived"
+    fn details(&self) -> String {
+        let length = self.duration.map(format_duration).unwrap_or_else(|| String::from("length unknown"));
+        if self.archived { format! ("{}, archived", length) } else { length }
+    }
+}
+
+fn videos(count: usize) -> String {
+    if count == 1 { String::from("1 video") } else { format! ("{} videos", count) }
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_[]()#<>".contains(c) { escaped.push('\\'); }
+        escaped.push(c);
+    }
+    escaped
+}
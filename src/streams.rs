@@ -0,0 +1,245 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use crate::config;
+use crate::notif::EventKind;
+use crate::youtube::{self, Video, Channel};
+use std::path::{PathBuf, Path};
+
+// Streams being watched for going live and ending, so a restart picks up where it left off
+const STREAMS_FILE_NAME: &str = "streams.json";
+// Give up on a stream we haven't been able to check for this long
+const GIVE_UP_SECS: i64 = 2 * 24 * 60 * 60;
+// A live stream that can't be checked for this long has ended; its VOD was likely disabled or made private
+const ENDED_GRACE_SECS: i64 = 10 * 60;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrackedStream {
+    pub video: Video,
+    pub channel: Channel,
+    // When it went live; None while it's still scheduled
+    pub started_at: Option<i64>,
+    pub reminded: bool,
+    // The last time youtube-dl could tell us anything about it
+    pub last_seen: i64
+}
+
+// Follows premieres and live streams from scheduled, to live, to ended
+pub struct StreamTracker {
+    path: PathBuf,
+    streams: Vec<TrackedStream>
+}
+
+impl StreamTracker {
+    pub fn load(cfg_path: &Path) -> StreamTracker {
+        let mut path = config::state_dir(cfg_path);
+        path.push(STREAMS_FILE_NAME);
+
+        let streams = config::load_state(&path, "tracked streams");
+        StreamTracker { path, streams }
+    }
+
+    // Start following the video if it's a stream that hasn't finished. If it's already about to
+    // start there's no reminder; the notification for finding it says as much
+    pub fn track(&mut self, vid: &Video, channel: &Channel, reminder_secs: i64, now: i64) {
+        if !vid.is_live && !vid.is_upcoming { return; }
+        if self.streams.iter().any(|stream| stream.video.video_id == vid.video_id) { return; }
+
+        self.streams.push(TrackedStream {
+            video: vid.clone(),
+            channel: channel.clone(),
+            started_at: if vid.is_live { Some(live_since(vid, now)) } else { None },
+            reminded: vid.is_upcoming && starts_soon(vid, reminder_secs, now),
+            last_seen: now
+        });
+        self.save_or_warn();
+    }
+
+    // Check up on every stream, giving back what's happened since last time
+    pub fn poll(&mut self, reminder_secs: i64, now: i64) -> Vec<(EventKind, Video, Channel)> {
+        self.poll_with(reminder_secs, now, youtube::populate_video_from_id)
+    }
+
+    // Like poll, getting each video's latest details from fetch
+    fn poll_with<F>(&mut self, reminder_secs: i64, now: i64, fetch: F) -> Vec<(EventKind, Video, Channel)>
+        where F: Fn(&String) -> Result<Video, ()>
+    {
+        let mut events = Vec::new();
+        let mut changed = false;
+
+        self.streams.retain_mut(|stream| {
+            let vid = if let Ok(vid) = fetch(&stream.video.video_id) { vid } else {
+                if let Some(started_at) = stream.started_at {
+                    if now - stream.last_seen >= ENDED_GRACE_SECS {
+                        // It was live the last time we could see it
+                        let mut ended = stream.video.clone();
+                        ended.is_live = false;
+                        ended.duration = Some((stream.last_seen - started_at).max(0) as u64);
+                        events.push((EventKind::Ended, ended, stream.channel.clone()));
+                        changed = true;
+                        return false;
+                    }
+                }
+                if now - stream.last_seen < GIVE_UP_SECS { return true; }
+                eprintln! ("Could not check on {} for too long; not following it any more", stream.video.video_title);
+                changed = true;
+                return false;
+            };
+            stream.last_seen = now;
+
+            match stream.started_at {
+                None if vid.is_live => {
+                    stream.started_at = Some(live_since(&vid, now));
+                    events.push((EventKind::Live, vid.clone(), stream.channel.clone()));
+                },
+                None if vid.is_upcoming => {
+                    if starts_soon(&vid, reminder_secs, now) && !stream.reminded {
+                        stream.reminded = true;
+                        events.push((EventKind::Reminder, vid.clone(), stream.channel.clone()));
+                    }
+                },
+                // Premieres can skip straight to being a normal video, and streams get cancelled
+                None => { changed = true; return false; },
+                Some(_) if vid.is_live => {  },
+                Some(started_at) => {
+                    // The VOD's length is the real thing if it's there yet
+                    let mut ended = vid.clone();
+                    ended.duration = vid.duration.filter(|duration| *duration > 0).or(Some((now - started_at).max(0) as u64));
+                    events.push((EventKind::Ended, ended, stream.channel.clone()));
+                    changed = true;
+                    return false;
+                }
+            }
+            stream.video = vid;
+            true
+        });
+
+        if changed || !events.is_empty() { self.save_or_warn(); }
+        events
+    }
+
+    fn save_or_warn(&self) {
+        if self.save().is_err() { eprintln! ("Could not save the tracked streams to {}", self.path.display()); }
+    }
+
+    fn save(&self) -> Result<(), ()> {
+        config::save_state(&self.path, &self.streams)
+    }
+}
+
+// Whether a scheduled stream is close enough to its start for a reminder
+fn starts_soon(vid: &Video, reminder_secs: i64, now: i64) -> bool {
+    reminder_secs > 0 && vid.published.is_some_and(|start| start - now <= reminder_secs)
+}
+
+// When a live stream started, going by youtube-dl if it knows; otherwise we've only just noticed
+fn live_since(vid: &Video, now: i64) -> i64 {
+    vid.published.filter(|start| *start <= now).unwrap_or(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notif::testing;
+    use std::cell::RefCell;
+
+    fn tracker(name: &str) -> StreamTracker {
+        let dir = std::env::temp_dir().join(format! ("yt-notify-streams-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        StreamTracker::load(&dir)
+    }
+
+    fn stream(live: bool, upcoming: bool, start: Option<i64>) -> Video {
+        let mut vid = testing::video();
+        vid.is_live = live;
+        vid.is_upcoming = upcoming;
+        vid.duration = None;
+        vid.published = start;
+        vid
+    }
+
+    fn kinds(events: &[(EventKind, Video, Channel)]) -> Vec<EventKind> {
+        events.iter().map(|(kind, _, _)| *kind).collect()
+    }
+
+    #[test]
+    fn follows_a_premiere_from_scheduled_to_ended() {
+        let mut tracker = tracker("premiere");
+        tracker.track(&stream(false, true, Some(10_000)), &testing::channel(), 1800, 0);
+        let now_video = RefCell::new(stream(false, true, Some(10_000)));
+        let fetch = |_: &String| Ok(now_video.borrow().clone());
+
+        assert! (tracker.poll_with(1800, 5_000, fetch).is_empty());
+        assert_eq! (kinds(&tracker.poll_with(1800, 8_500, fetch)), [EventKind::Reminder]);
+        // Only reminded once
+        assert! (tracker.poll_with(1800, 9_000, fetch).is_empty());
+
+        *now_video.borrow_mut() = stream(true, false, Some(10_000));
+        assert_eq! (kinds(&tracker.poll_with(1800, 10_050, fetch)), [EventKind::Live]);
+        assert! (tracker.poll_with(1800, 11_000, fetch).is_empty());
+
+        *now_video.borrow_mut() = stream(false, false, Some(10_000));
+        let events = tracker.poll_with(1800, 13_650, fetch);
+        assert_eq! (kinds(&events), [EventKind::Ended]);
+        // From when it actually started, not when it was noticed
+        assert_eq! (events[0].1.duration, Some(3_650));
+        assert! (tracker.streams.is_empty());
+    }
+
+    #[test]
+    fn premieres_found_close_to_their_start_get_no_reminder() {
+        let mut tracker = tracker("found-late");
+        tracker.track(&stream(false, true, Some(10_000)), &testing::channel(), 1800, 9_000);
+        assert! (tracker.poll_with(1800, 9_015, |_| Ok(stream(false, true, Some(10_000)))).is_empty());
+        assert_eq! (kinds(&tracker.poll_with(1800, 10_015, |_| Ok(stream(true, false, Some(10_000))))), [EventKind::Live]);
+    }
+
+    #[test]
+    fn streams_noticed_part_way_through_count_from_their_start() {
+        let mut tracker = tracker("midway");
+        tracker.track(&stream(true, false, Some(500)), &testing::channel(), 0, 2_000);
+
+        let events = tracker.poll_with(0, 4_000, |_| Ok(stream(false, false, Some(500))));
+        assert_eq! (events[0].1.duration, Some(3_500));
+
+        // Once the VOD's up its length wins
+        tracker.track(&stream(true, false, Some(500)), &testing::channel(), 0, 2_000);
+        let mut vod = stream(false, false, Some(500));
+        vod.duration = Some(3_333);
+        let events = tracker.poll_with(0, 4_000, |_| Ok(vod.clone()));
+        assert_eq! (events[0].1.duration, Some(3_333));
+    }
+
+    #[test]
+    fn a_live_stream_that_disappears_has_ended() {
+        let mut tracker = tracker("disappears");
+        tracker.track(&stream(true, false, None), &testing::channel(), 0, 1_000);
+        assert! (tracker.poll_with(0, 1_100, |_| Ok(stream(true, false, None))).is_empty());
+
+        // Just a blip
+        assert! (tracker.poll_with(0, 1_100 + ENDED_GRACE_SECS - 1, |_| Err(())).is_empty());
+        let events = tracker.poll_with(0, 1_100 + ENDED_GRACE_SECS, |_| Err(()));
+        assert_eq! (kinds(&events), [EventKind::Ended]);
+        // Live until the last time it could be seen
+        assert_eq! (events[0].1.duration, Some(100));
+        assert! (tracker.streams.is_empty());
+    }
+
+    #[test]
+    fn a_scheduled_stream_that_disappears_is_given_up_on_quietly() {
+        let mut tracker = tracker("scheduled");
+        tracker.track(&stream(false, true, Some(100_000)), &testing::channel(), 0, 0);
+
+        assert! (tracker.poll_with(0, ENDED_GRACE_SECS * 2, |_| Err(())).is_empty());
+        assert_eq! (tracker.streams.len(), 1);
+        assert! (tracker.poll_with(0, GIVE_UP_SECS, |_| Err(())).is_empty());
+        assert! (tracker.streams.is_empty());
+    }
+
+    #[test]
+    fn tracked_streams_survive_a_restart() {
+        let dir = std::env::temp_dir().join(format! ("yt-notify-streams-{}-restart", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        StreamTracker::load(&dir).track(&stream(true, false, None), &testing::channel(), 0, 0);
+        assert_eq! (StreamTracker::load(&dir).streams.len(), 1);
+    }
+}
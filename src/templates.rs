@@ -0,0 +1,143 @@
+extern crate serde;
+use serde::{Serialize, Deserialize};
+use crate::notif::EventKind;
+
+// Everything that can go between braces; "{{" and "}}" are literal braces
+pub const PLACEHOLDERS: [&str; 6] = ["title", "channel", "duration", "published", "url", "matched_keyword"];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Template {
+    pub summary: String,
+    pub body: String
+}
+
+// A template for each kind of event; the next level up is used for any left as None
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Templates {
+    pub upload: Option<Template>,
+    pub live: Option<Template>,
+    pub premiere: Option<Template>,
+    pub reminder: Option<Template>,
+    pub ended: Option<Template>
+}
+
+// What the placeholders are filled in with
+pub struct TemplateValues<'a> {
+    pub title: &'a str,
+    pub channel: &'a str,
+    pub duration: String,
+    pub published: String,
+    pub url: String,
+    pub matched_keyword: String
+}
+
+impl Template {
+    fn new(summary: &str, body: &str) -> Template {
+        Template { summary: String::from(summary), body: String::from(body) }
+    }
+
+    // What notifications said before templates existed
+    pub fn default_for(kind: EventKind) -> Template {
+        match kind {
+            EventKind::Upload => Template::new("{title}", "{channel} has uploaded a video"),
+            EventKind::Live => Template::new("{title}", "{channel} is live"),
+            EventKind::Premiere => Template::new("{title}", "{channel} has scheduled a premiere for {published}"),
+            EventKind::Reminder => Template::new("{title}", "{channel} goes live at {published}"),
+            EventKind::Ended => Template::new("{title}", "{channel}'s stream ended ({duration})")
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        validate(&self.summary)?;
+        validate(&self.body)
+    }
+
+    // Fill in the placeholders, giving (summary, body)
+    pub fn render(&self, values: &TemplateValues) -> (String, String) {
+        (render(&self.summary, values), render(&self.body, values))
+    }
+}
+
+impl Templates {
+    pub fn get(&self, kind: EventKind) -> Option<&Template> {
+        match kind {
+            EventKind::Upload => self.upload.as_ref(),
+            EventKind::Live => self.live.as_ref(),
+            EventKind::Premiere => self.premiere.as_ref(),
+            EventKind::Reminder => self.reminder.as_ref(),
+            EventKind::Ended => self.ended.as_ref()
+        }
+    }
+
+    // Check every template, saying which one is wrong
+    pub fn validate(&self) -> Result<(), String> {
+        let all = [("upload", &self.upload), ("live", &self.live), ("premiere", &self.premiere), ("reminder", &self.reminder), ("ended", &self.ended)];
+        for (name, template) in all.iter() {
+            if let Some(template) = template {
+                template.validate().map_err(|e| format! ("{} template: {}", name, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Make sure every brace is closed and every placeholder is one we know
+pub fn validate(template: &str) -> Result<(), String> {
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => { chars.next(); },
+            '}' if chars.peek() == Some(&'}') => { chars.next(); },
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format! ("unclosed {{ in \"{}\"", template))
+                    }
+                }
+                if !PLACEHOLDERS.contains(&name.as_str()) {
+                    return Err(format! ("unknown placeholder {{{}}} in \"{}\"; use one of {}", name, template, PLACEHOLDERS.join(", ")));
+                }
+            },
+            '}' => return Err(format! ("unmatched }} in \"{}\"", template)),
+            _ => {  }
+        }
+    }
+    Ok(())
+}
+
+pub fn render(template: &str, values: &TemplateValues) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => { chars.next(); out.push('{'); },
+            '}' if chars.peek() == Some(&'}') => { chars.next(); out.push('}'); },
+            '{' => {
+                let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                match name.as_str() {
+                    "title" => out.push_str(values.title),
+                    "channel" => out.push_str(values.channel),
+                    "duration" => out.push_str(&values.duration),
+                    "published" => out.push_str(&values.published),
+                    "url" => out.push_str(&values.url),
+                    "matched_keyword" => out.push_str(&values.matched_keyword),
+                    // Templates are validated on load, but don't lose anything if one slips through
+                    _ => { out.push('{'); out.push_str(&name); out.push('}'); }
+                }
+            },
+            _ => out.push(c)
+        }
+    }
+    out
+}
+
+// 754 -> "12:34", 3754 -> "1:02:34"
+pub fn format_duration(secs: u64) -> String {
+    let (hours, mins, secs) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if hours > 0 { format! ("{}:{:02}:{:02}", hours, mins, secs) }
+    else { format! ("{}:{:02}", mins, secs) }
+}
@@ -0,0 +1,75 @@
+extern crate easy_http_request;
+
+// Get the URL YouTube serves the video's thumbnail from
+pub fn thumbnail_url(video_id: &str) -> String {
+    format! ("https://i.ytimg.com/vi/{}/mqdefault.jpg", video_id)
+}
+
+// The thumbnails shown in desktop notifications, kept on disk so each is only downloaded once
+#[cfg(feature = "desktop")]
+mod cache {
+    use easy_http_request::DefaultHttpRequest;
+    use std::path::{PathBuf, Path};
+    use std::time::SystemTime;
+    use super::thumbnail_url;
+
+    // Get the directory thumbnails are cached in
+    fn thumbnail_dir(cfg_path: &Path) -> PathBuf {
+        let mut path = cfg_path.to_path_buf();
+        path.push("thumbnails");
+        path
+    }
+
+    // Get the video's thumbnail from the cache, downloading it if it isn't there
+    pub fn cached_thumbnail(cfg_path: &Path, video_id: &str, max_mb: u64) -> Option<PathBuf> {
+        let dir = thumbnail_dir(cfg_path);
+        let mut path = dir.clone();
+        path.push(format! ("{}.jpg", video_id));
+
+        if path.exists() {
+            // Bump it to the front of the LRU
+            touch(&path);
+            return Some(path);
+        }
+
+        std::fs::create_dir_all(&dir).ok()?;
+        let response = DefaultHttpRequest::get_from_url_str(thumbnail_url(video_id)).ok()?.send().ok()?;
+        if response.status_code != 200 { return None; }
+        std::fs::write(&path, response.body).ok()?;
+
+        evict(&dir, max_mb * 1024 * 1024, &path);
+        Some(path)
+    }
+
+    // Delete the least recently used thumbnails until the cache fits in max_bytes
+    fn evict(dir: &Path, max_bytes: u64, keep: &Path) {
+        let entries = if let Ok(entries) = std::fs::read_dir(dir) { entries } else { return; };
+
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = Vec::new();
+        for entry in entries.flatten() {
+            if let Ok(meta) = entry.metadata() {
+                if !meta.is_file() { continue; }
+                let used = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.push((used, meta.len(), entry.path()));
+            }
+        }
+
+        let mut total: u64 = files.iter().map(|f| f.1).sum();
+        files.sort_by_key(|f| f.0);
+        for (_, size, path) in files.iter() {
+            if total <= max_bytes { break; }
+            // Never throw away the one we're about to show
+            if path == keep { continue; }
+            if std::fs::remove_file(path).is_ok() { total -= size; }
+        }
+    }
+
+    // Mark a thumbnail as just used
+    fn touch(path: &Path) {
+        if let Ok(file) = std::fs::File::options().write(true).open(path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+    }
+}
+#[cfg(feature = "desktop")]
+pub use cache::cached_thumbnail;
@@ -10,6 +10,8 @@ use std::io::Write;
 use std::io::Read;
 use std::vec::Vec;
 use std::str;
+use crate::notif::{MessageFormat, ChannelPrefs};
+use crate::templates::Templates;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 enum ChannelType {
@@ -18,13 +20,19 @@ enum ChannelType {
     C
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Video {
     pub video_title: String,
     pub video_id: String,
     pub video_desc: String,
     pub is_live: bool,
-    pub tags: Option<Vec<String>>
+    pub tags: Option<Vec<String>>,
+    // Premieres and scheduled streams that haven't started yet
+    pub is_upcoming: bool,
+    // In seconds; None for lives and premieres
+    pub duration: Option<u64>,
+    // Unix time it went up, or when it's scheduled to start if it's upcoming
+    pub published: Option<i64>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,17 +40,34 @@ pub struct Channel {
     pub name: String,
     pub channel_id: String,
     channel_type: ChannelType,
-    filter: Vec<String>,
+    pub filter: Vec<String>,
     path: PathBuf,
     pub pic_path: PathBuf,
     pub archive: bool,
     pub archive_filter: Option<Vec<String>>,
-    latest_ids: (Option<String>, Option<String>)
+    latest_ids: (Option<String>, Option<String>),
+    #[serde(default)]
+    muted_until: Option<i64>,
+    // Names of the notifiers to use; all of them if None
+    #[serde(default)]
+    pub notifiers: Option<Vec<String>>,
+    // How chat webhooks (Discord, Slack) lay out messages; the notifier's setting if None
+    #[serde(default)]
+    pub message_format: Option<MessageFormat>,
+    // Overrides for the notification text; the config's templates are used for any left out
+    #[serde(default)]
+    pub templates: Templates,
+    // Urgency, timeout, sound and so on, for every event or just one kind
+    #[serde(default)]
+    pub prefs: ChannelPrefs,
+    // Send notifications even during quiet hours
+    #[serde(default)]
+    pub break_quiet_hours: bool
 }
 
 impl Channel {
     // Create a new Channel
-    pub fn new(channel_name: String, channel_url: String, base_path: &PathBuf, filter_words: Vec<String>, to_archive: bool, a_filters: Option<Vec<String>>) -> Result<Channel, ()> {
+    pub fn new(channel_name: String, channel_url: String, base_path: &Path, filter_words: Vec<String>, to_archive: bool, a_filters: Option<Vec<String>>) -> Result<Channel, ()> {
 
         // Throw a tantrum if any of the args are empty
         if &channel_name == "" || &channel_url == "" { return Err(()); }
@@ -66,11 +91,13 @@ impl Channel {
         };
 
         // Set up the paths
-        let mut cfg_path = base_path.clone();
+        let mut cfg_path = base_path.to_path_buf();
         cfg_path.push(format!("{}.json", id));
+        // Only clean up after ourselves if it couldn't be verified; never a file that was already there
+        let existed = cfg_path.exists();
         if let Err(_) = std::fs::File::create(&cfg_path) { return Err(()); }
 
-        let mut pic_path = base_path.clone();
+        let mut pic_path = base_path.to_path_buf();
         pic_path.push("icons");
         pic_path.push(format!("{}.png", id));
         
@@ -85,13 +112,20 @@ impl Channel {
             pic_path: pic_path,
             archive: to_archive,
             archive_filter: a_filters,
-            latest_ids: (None, None)
+            latest_ids: (None, None),
+            muted_until: None,
+            notifiers: None,
+            message_format: None,
+            templates: Templates::default(),
+            prefs: ChannelPrefs::default(),
+            break_quiet_hours: false
         };
 
         // If it's a C type channel, get the true ID and assign the latest video id
         if let ChannelType::C = ret_channel.channel_type {
             if let Err(_) = ret_channel.get_true_channel() {
                 // For some reason, we couldn't update the channel ID
+                if !existed { let _ = std::fs::remove_file(&ret_channel.path); }
                 return Err(());
             }
         }
@@ -102,7 +136,10 @@ impl Channel {
         if let (Ok(latest_found_id_1), Ok(latest_found_id_2)) = (ret_channel.get_vid_id_from_index(0), ret_channel.get_vid_id_from_index(1)) {
             ret_channel.latest_ids = (Some(latest_found_id_1), Some(latest_found_id_2));
             Ok(ret_channel)
-        } else { Err(()) }
+        } else {
+            if !existed { let _ = std::fs::remove_file(&ret_channel.path); }
+            Err(())
+        }
 
     } // end new
 
@@ -129,6 +166,33 @@ impl Channel {
         }
     } // end get_req_url
 
+    // Get the URL of the channel's page
+    pub fn get_channel_url(&self) -> String {
+        match self.channel_type {
+            ChannelType::Channel => { format!("https://www.youtube.com/channel/{}", self.channel_id) },
+            ChannelType::User => { format!("https://www.youtube.com/user/{}", self.channel_id) },
+            ChannelType::C => { format!("https://www.youtube.com/c/{}", self.channel_id) }
+        }
+    } // end get_channel_url
+
+    // Where the channel gets linked to; the Invidious or Piped instance's page for it if there is one
+    pub fn link_url(&self, frontend: Option<&str>) -> String {
+        let instance = if let Some(instance) = frontend { instance.trim_end_matches('/') } else { return self.get_channel_url(); };
+        match self.channel_type {
+            ChannelType::Channel => { format!("{}/channel/{}", instance, self.channel_id) },
+            ChannelType::User => { format!("{}/user/{}", instance, self.channel_id) },
+            ChannelType::C => { format!("{}/c/{}", instance, self.channel_id) }
+        }
+    } // end link_url
+
+    // Get the URL of the channel's Atom feed
+    pub fn get_atom_url(&self) -> String {
+        match self.channel_type {
+            ChannelType::User => { format!("https://www.youtube.com/feeds/videos.xml?user={}", self.channel_id) },
+            _ => { format!("https://www.youtube.com/feeds/videos.xml?channel_id={}", self.channel_id) }
+        }
+    } // end get_atom_url
+
     // Get the latest video ID
     pub fn get_vid_id_from_index(&self, index: u8) -> Result<String, ()> {
         // Make the command, execute it and get the stdout
@@ -195,6 +259,24 @@ impl Channel {
         } else { Err(()) }
     }
 
+    // Silence the channel for a while; re-read first so we don't clobber newer ids
+    #[cfg(feature = "desktop")]
+    pub fn mute_for(&self, secs: u64) -> Result<(), ()> {
+        let mut updated_ch = Channel::from_file(&self.path)?;
+        updated_ch.muted_until = Some(chrono::Utc::now().timestamp() + secs as i64);
+        updated_ch.write_channel_to_file()
+    }
+
+    pub fn is_muted(&self) -> bool {
+        if let Some(until) = self.muted_until { chrono::Utc::now().timestamp() < until }
+        else { false }
+    }
+
+    pub fn uses_notifier(&self, name: &str) -> bool {
+        if let Some(names) = &self.notifiers { names.iter().any(|n| n == name) }
+        else { true }
+    }
+
     pub fn passes_filter(&self, vid: &Video) -> bool {
         if self.filter.len() == 0 { return true; }
         
@@ -204,6 +286,26 @@ impl Channel {
         }
         show
     }
+
+    // The first filter word the video matched on, for templates to mention
+    pub fn matched_keyword(&self, vid: &Video) -> Option<String> {
+        self.filter.iter()
+            .find(|filter| vid.video_title.contains(filter.as_str()) || vid.video_desc.contains(filter.as_str()))
+            .cloned()
+    }
+}
+
+// Where a video can be watched
+pub fn watch_url(video_id: &str) -> String {
+    format! ("https://www.youtube.com/watch?v={}", video_id)
+}
+
+// Where a video gets linked to; the Invidious or Piped instance's page for it if there is one
+pub fn link_url(video_id: &str, frontend: Option<&str>) -> String {
+    match frontend {
+        Some(instance) => format! ("{}/watch?v={}", instance.trim_end_matches('/'), video_id),
+        None => watch_url(video_id)
+    }
 }
 
 pub fn populate_video_from_id(id: &String) -> Result<Video, ()> {
@@ -233,13 +335,26 @@ pub fn populate_video_from_id(id: &String) -> Result<Video, ()> {
             let split = bracketless_tags.split("\",\"");
             split.map(|st| String::from(st)).collect::<Vec<String>>()
         };
+        let upcoming = parsed_out["live_status"].as_str() == Some("is_upcoming");
+        let duration = parsed_out["duration"].as_u64();
+        // Upcoming videos only know when they'll start and streams when they started; older youtube-dl builds only give a date
+        let published = parsed_out["release_timestamp"].as_i64()
+            .filter(|_| upcoming || live)
+            .or_else(|| parsed_out["timestamp"].as_i64())
+            .or_else(|| parsed_out["upload_date"].as_str()
+                .and_then(|date| chrono::NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|date| date.and_utc().timestamp()));
 
         Ok(Video {
             video_title: String::from(title),
             video_id: String::from(id),
             video_desc: String::from(desc),
             is_live: live,
-            tags: Some(tags)
+            tags: Some(tags),
+            is_upcoming: upcoming,
+            duration,
+            published
         })
     } else { Err(()) }
 }